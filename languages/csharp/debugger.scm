@@ -0,0 +1,36 @@
+; Variables whose values are shown inline while stepping through a debug session
+
+; Declarations
+(parameter name: (identifier) @debug-variable)
+(variable_declarator (identifier) @debug-variable)
+(foreach_statement (identifier) @debug-variable)
+(catch_declaration (_) (identifier) @debug-variable)
+
+; Uses (PascalCase names are usually types or constants)
+(assignment_expression
+  left: (identifier) @debug-variable
+  (#not-match? @debug-variable "^[A-Z]"))
+
+(binary_expression
+  (identifier) @debug-variable
+  (#not-match? @debug-variable "^[A-Z]"))
+
+(argument
+  (identifier) @debug-variable
+  (#not-match? @debug-variable "^[A-Z]"))
+
+(return_statement
+  (identifier) @debug-variable
+  (#not-match? @debug-variable "^[A-Z]"))
+
+; Scopes the variables above belong to
+[
+  (block)
+  (method_declaration)
+  (constructor_declaration)
+  (local_function_statement)
+  (lambda_expression)
+  (for_statement)
+  (foreach_statement)
+  (catch_clause)
+] @debug-scope
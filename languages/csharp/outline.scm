@@ -0,0 +1,61 @@
+; Namespaces
+(namespace_declaration
+  "namespace" @context
+  name: (_) @name) @item
+
+(file_scoped_namespace_declaration
+  "namespace" @context
+  name: (_) @name) @item
+
+; Types
+(class_declaration
+  "class" @context
+  name: (identifier) @name) @item
+
+(struct_declaration
+  "struct" @context
+  (identifier) @name) @item
+
+(interface_declaration
+  "interface" @context
+  name: (identifier) @name) @item
+
+(record_declaration
+  "record" @context
+  (identifier) @name) @item
+
+(enum_declaration
+  "enum" @context
+  name: (identifier) @name) @item
+
+(delegate_declaration
+  "delegate" @context
+  name: (identifier) @name) @item
+
+; Members
+(enum_member_declaration
+  (identifier) @name) @item
+
+(constructor_declaration
+  name: (identifier) @name) @item
+
+(destructor_declaration
+  "~" @context
+  name: (identifier) @name) @item
+
+(method_declaration
+  name: (identifier) @name) @item
+
+(property_declaration
+  name: (identifier) @name) @item
+
+(field_declaration
+  (variable_declaration
+    (variable_declarator
+      (identifier) @name))) @item
+
+(event_field_declaration
+  "event" @context
+  (variable_declaration
+    (variable_declarator
+      (identifier) @name))) @item
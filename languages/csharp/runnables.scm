@@ -0,0 +1,50 @@
+; Test methods: xUnit [Fact]/[Theory], NUnit [Test]/[TestCase]/[TestCaseSource], MSTest [TestMethod]/[DataTestMethod]
+(
+  (class_declaration
+    name: (identifier) @_class_name
+    body: (declaration_list
+      (method_declaration
+        (attribute_list
+          (attribute
+            name: (identifier) @_attribute))
+        name: (identifier) @run @_method_name)))
+  (#any-of? @_attribute "Fact" "Theory" "Test" "TestCase" "TestCaseSource" "TestMethod" "DataTestMethod")
+  (#set! tag csharp-test)
+)
+
+; Test classes: NUnit [TestFixture], MSTest [TestClass]
+(
+  (class_declaration
+    (attribute_list
+      (attribute
+        name: (identifier) @_attribute))
+    name: (identifier) @run @_class_name)
+  (#any-of? @_attribute "TestFixture" "TestClass")
+  (#set! tag csharp-test-class)
+)
+
+; Entry points: static Main methods
+(
+  (method_declaration
+    (modifier) @_modifier
+    name: (identifier) @run @_method_name)
+  (#eq? @_modifier "static")
+  (#eq? @_method_name "Main")
+  (#set! tag csharp-main)
+)
+
+; Top-level programs: the first statement after the using directives
+(
+  (compilation_unit
+    (using_directive)
+    .
+    (global_statement) @run)
+  (#set! tag csharp-main)
+)
+
+(
+  (compilation_unit
+    .
+    (global_statement) @run)
+  (#set! tag csharp-main)
+)
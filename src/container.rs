@@ -0,0 +1,240 @@
+use std::path::Path;
+use zed_extension_api::{self as zed, Result, Worktree};
+
+use crate::debugger;
+use crate::logging::debug_log;
+use crate::processes::command_on_path;
+use crate::settings::RoslynSettings;
+
+/// Where the debugger directory is mounted inside the container
+const CONTAINER_DEBUGGER_DIR: &str = "/netcoredbg";
+
+/// Settings of a `launchInContainer` debug configuration
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContainerLaunch {
+    /// Build with `dotnet publish -t:PublishContainer` for this project instead of a Dockerfile
+    pub project: Option<String>,
+    pub dockerfile: String,
+    pub context: String,
+    pub image: String,
+    pub container_name: String,
+    /// Port to publish, in `docker run -p` form (`8080:8080`)
+    pub port: Option<String>,
+    /// Host directory holding a Linux netcoredbg, mounted into the container
+    pub debugger_path: Option<String>,
+    /// PID of the app inside the container; 1 when the app is the entrypoint
+    pub process_id: u32,
+}
+
+impl ContainerLaunch {
+    /// Read `launchInContainer` from a debug configuration, filling defaults from the worktree name.
+    /// Returns `Ok(None)` for configurations without it.
+    pub fn from_config(config: &serde_json::Value, worktree_name: &str) -> Result<Option<Self>> {
+        let Some(launch) = config.get("launchInContainer") else {
+            return Ok(None);
+        };
+        let string = |key: &str| launch.get(key).and_then(|v| v.as_str()).map(|v| v.to_string());
+
+        let image = string("image").unwrap_or_else(|| {
+            let name: String = worktree_name
+                .to_lowercase()
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' { c } else { '-' })
+                .collect();
+            format!("{}-debug", name.trim_matches('-'))
+        });
+        let process_id = match config.get("processId") {
+            None => 1,
+            Some(pid) => pid
+                .as_u64()
+                .and_then(|pid| u32::try_from(pid).ok())
+                .ok_or_else(|| format!("Invalid processId: {pid}"))?,
+        };
+        Ok(Some(Self {
+            project: string("project"),
+            dockerfile: string("dockerfile").unwrap_or_else(|| "Dockerfile".to_string()),
+            context: string("context").unwrap_or_else(|| ".".to_string()),
+            container_name: string("containerName").unwrap_or_else(|| image.clone()),
+            image,
+            port: string("port"),
+            debugger_path: string("debuggerPath"),
+            process_id,
+        }))
+    }
+
+    /// Arguments for `docker run`: detached, named, with the debugger mounted read-only. Only the
+    /// port is configurable, since the `process:exec` capability fixes the argument list.
+    pub fn run_args(&self, debugger_dir: &str) -> Vec<String> {
+        let mut args = vec![
+            "run".to_string(),
+            "-d".to_string(),
+            "--name".to_string(),
+            self.container_name.clone(),
+            "-v".to_string(),
+            format!("{debugger_dir}:{CONTAINER_DEBUGGER_DIR}:ro"),
+        ];
+        if let Some(port) = &self.port {
+            args.extend(["-p".to_string(), port.clone()]);
+        }
+        args.push(self.image.clone());
+        args
+    }
+
+    /// The attach configuration that replaces the launch: netcoredbg runs inside the container
+    /// through `docker exec` and attaches to the app
+    pub fn attach_config(&self, docker: &str) -> serde_json::Value {
+        serde_json::json!({
+            "request": "attach",
+            "processId": self.process_id,
+            "pipeTransport": {
+                "pipeProgram": docker,
+                "pipeArgs": ["exec", "-i", self.container_name],
+                "debuggerPath": format!("{CONTAINER_DEBUGGER_DIR}/netcoredbg"),
+            },
+        })
+    }
+}
+
+fn run(program: &str, args: &[String], worktree: &Worktree) -> Result<()> {
+    debug_log!(worktree, "[csharp_roslyn] {program} {}", args.join(" "));
+    let output = command_on_path(worktree, program)
+        .args(args.iter().cloned())
+        .output()
+        .map_err(|e| format!("Failed to run {program} {}: {e}", args.join(" ")))?;
+    if output.status != Some(0) {
+        return Err(format!(
+            "{program} {} failed:\n{}{}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    Ok(())
+}
+
+/// Directory of a Linux netcoredbg to mount. The extension's own download only fits when the
+/// host is Linux too; otherwise `debuggerPath` has to point at one.
+fn debugger_dir(launch: &ContainerLaunch, worktree: &Worktree, settings: &RoslynSettings) -> Result<String> {
+    if let Some(path) = &launch.debugger_path {
+        return Ok(path.clone());
+    }
+    let (platform, _) = zed::current_platform();
+    if platform != zed::Os::Linux {
+        return Err("launchInContainer needs \"debuggerPath\": a host directory with a Linux build of netcoredbg".to_string());
+    }
+    let command = debugger::ensure_debugger(worktree, settings)?;
+    Path::new(&command.command)
+        .parent()
+        .map(|dir| dir.to_string_lossy().to_string())
+        .ok_or_else(|| format!("Unexpected debugger path: {}", command.command))
+}
+
+/// Turn a `launchInContainer` configuration into an attach over `docker exec`: build the image
+/// (Dockerfile or `dotnet publish -t:PublishContainer`), replace any previous debug container,
+/// and start a new one with netcoredbg mounted. Other configurations are left untouched.
+pub fn prepare_container_launch(
+    config: &mut serde_json::Value,
+    worktree: &Worktree,
+    settings: &RoslynSettings,
+) -> Result<()> {
+    let root_path = worktree.root_path();
+    let worktree_name = Path::new(&root_path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "app".to_string());
+    let Some(launch) = ContainerLaunch::from_config(config, &worktree_name)? else {
+        return Ok(());
+    };
+    if config.pointer("/launchInContainer/runArgs").is_some() {
+        return Err("launchInContainer.runArgs is no longer supported; publish a port with \"port\": \"8080:8080\"".to_string());
+    }
+    // Run docker by name to match the extension's capabilities; the resolved path is only
+    // handed to netcoredbg as its pipe program
+    let docker = worktree
+        .which("docker")
+        .ok_or_else(|| "launchInContainer requires docker on PATH".to_string())?;
+    let debugger_dir = debugger_dir(&launch, worktree, settings)?;
+    let in_root = |path: &str| Path::new(&root_path).join(path).to_string_lossy().to_string();
+
+    match &launch.project {
+        Some(project) => {
+            worktree
+                .which("dotnet")
+                .ok_or_else(|| "dotnet was not found on PATH".to_string())?;
+            run(
+                "dotnet",
+                &[
+                    "publish".to_string(),
+                    in_root(project),
+                    "-t:PublishContainer".to_string(),
+                    format!("-p:ContainerRepository={}", launch.image),
+                ],
+                worktree,
+            )?;
+        }
+        None => run(
+            "docker",
+            &[
+                "build".to_string(),
+                "-t".to_string(),
+                launch.image.clone(),
+                "-f".to_string(),
+                in_root(&launch.dockerfile),
+                in_root(&launch.context),
+            ],
+            worktree,
+        )?,
+    }
+
+    // A container left over from the previous session would hold the name
+    let _ = run("docker", &["rm".to_string(), "-f".to_string(), launch.container_name.clone()], worktree);
+    run("docker", &launch.run_args(&debugger_dir), worktree)?;
+
+    let mut attach = launch.attach_config(&docker);
+    if let (Some(attach), Some(original)) = (attach.as_object_mut(), config.as_object()) {
+        // Keep debug options such as justMyCode or sourceFileMap from the original configuration
+        for (key, value) in original {
+            if !matches!(key.as_str(), "launchInContainer" | "request" | "program" | "args" | "cwd" | "env") {
+                attach.entry(key.clone()).or_insert_with(|| value.clone());
+            }
+        }
+    }
+    *config = attach;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_container_launch_from_config() {
+        let config = serde_json::json!({
+            "request": "launch",
+            "launchInContainer": {
+                "dockerfile": "src/Api/Dockerfile",
+                "port": "8080:8080",
+                "debuggerPath": "/opt/netcoredbg"
+            }
+        });
+        let launch = ContainerLaunch::from_config(&config, "My Service").unwrap().unwrap();
+        assert_eq!(launch.image, "my-service-debug");
+        assert_eq!(launch.container_name, "my-service-debug");
+        assert_eq!(launch.context, ".");
+        assert_eq!(launch.process_id, 1);
+        assert_eq!(
+            launch.run_args("/opt/netcoredbg"),
+            vec!["run", "-d", "--name", "my-service-debug", "-v", "/opt/netcoredbg:/netcoredbg:ro", "-p", "8080:8080", "my-service-debug"]
+        );
+
+        let attach = launch.attach_config("docker");
+        assert_eq!(attach["request"], "attach");
+        assert_eq!(attach["pipeTransport"]["pipeArgs"], serde_json::json!(["exec", "-i", "my-service-debug"]));
+        assert_eq!(attach["pipeTransport"]["debuggerPath"], "/netcoredbg/netcoredbg");
+
+        assert!(ContainerLaunch::from_config(&serde_json::json!({ "request": "launch" }), "app").unwrap().is_none());
+
+        let config = serde_json::json!({ "launchInContainer": {}, "processId": 4294967297u64 });
+        assert!(ContainerLaunch::from_config(&config, "app").is_err());
+    }
+}
@@ -3,14 +3,30 @@ use std::path::{Path, PathBuf};
 use serde_json::json;
 use url::Url;
 use zed_extension_api::{
-    self as zed, settings::LspSettings, DebugAdapterBinary, DebugConfig, DebugRequest,
-    DebugScenario, DebugTaskDefinition, LanguageServerId, Result, StartDebuggingRequestArguments,
+    self as zed, BuildTaskDefinition, BuildTaskDefinitionTemplatePayload,
+    BuildTaskTemplate, DebugAdapterBinary, DebugConfig, DebugRequest,
+    DebugScenario, DebugTaskDefinition, LanguageServerId, Result, SlashCommand,
+    SlashCommandArgumentCompletion, SlashCommandOutput, StartDebuggingRequestArguments,
     StartDebuggingRequestArgumentsRequest, TaskTemplate,
 };
 
+use crate::container;
 use crate::debugger;
-use crate::logging::debug_log;
-use crate::project_info::{DotNetProject, is_unity_project, ensure_unity_project_files, get_unity_omnisharp_config};
+use crate::dotnet_runtime;
+use crate::dotnet_sdk;
+use crate::logging::{self, debug_log, StartupTimeline};
+use crate::msbuild;
+use crate::nuget;
+use crate::processes;
+use crate::scaffolding;
+use crate::settings::RoslynSettings;
+use crate::support;
+use crate::unity_debugger;
+use crate::project_info::{
+    DotNetProject, is_unity_project, ensure_unity_project_files, get_unity_omnisharp_config,
+    is_godot_project, get_godot_omnisharp_config, get_large_solution_omnisharp_config, LARGE_SOLUTION_PROJECT_COUNT, is_mobile_target_framework,
+    is_web_project, is_worker_project, parse_launch_settings, parse_user_secrets_list, user_secrets_id, LaunchProfile, solution_has_legacy_projects, solution_project_paths, with_directory_build_props,
+};
 
 pub struct CsharpRoslynExtension;
 
@@ -25,6 +41,13 @@ impl zed::Extension for CsharpRoslynExtension {
         worktree: &zed::Worktree,
     ) -> Result<zed::Command> {
         let (platform, arch) = zed::current_platform();
+        let settings = RoslynSettings::for_worktree(worktree);
+        let _log = logging::scope(worktree, &settings);
+        let mut timeline = StartupTimeline::start();
+
+        // Legacy .NET Framework solutions need the Framework build of OmniSharp (Mono outside Windows)
+        let use_modern_net = use_modern_net(worktree, &settings);
+        timeline.step("solution scan");
 
         // Download OmniSharp-Roslyn (with progress reporting)
         debug_log!(worktree, "[csharp_roslyn] Ensuring OmniSharp is available");
@@ -32,9 +55,12 @@ impl zed::Extension for CsharpRoslynExtension {
             language_server_id,
             platform,
             arch,
+            use_modern_net,
             worktree,
+            &settings,
         )?;
         debug_log!(worktree, "[csharp_roslyn] OmniSharp path: {omnisharp_path}");
+        timeline.step("OmniSharp install");
 
         // Run OmniSharp in LSP mode
         // OmniSharp will use the solution path from initialization_options
@@ -64,11 +90,59 @@ impl zed::Extension for CsharpRoslynExtension {
             }
         }
 
+        // Honor a global.json SDK pin by pointing OmniSharp at a compatible dotnet installation.
+        // Without a pin, fall back to a private runtime when no usable one is installed.
+        let pinned_sdk = dotnet_sdk::resolve_dotnet_for_worktree(worktree, &env)?;
+        let dotnet_root = match &pinned_sdk {
+            Some(install) => install.root.clone(),
+            None => dotnet_runtime::ensure_dotnet_runtime(language_server_id, worktree, &settings, &env)?,
+        };
+        if let Some(dotnet_root) = dotnet_root {
+            debug_log!(worktree, "[csharp_roslyn] Using dotnet installation: {dotnet_root}");
+            let path_separator = if platform == zed::Os::Windows { ";" } else { ":" };
+            let path = env
+                .iter()
+                .find(|(k, _)| k == "PATH")
+                .map(|(_, v)| format!("{dotnet_root}{path_separator}{v}"))
+                .unwrap_or_else(|| dotnet_root.clone());
+            set_env_var(&mut env, "PATH", path);
+            set_env_var(&mut env, "DOTNET_ROOT", dotnet_root);
+        }
+        timeline.step(".NET resolution");
+
+        // XAML code-behind and gRPC classes only exist after a build. Generating them before
+        // OmniSharp loads blocks startup, so it's opt-in.
+        if settings.design_time_builds {
+            run_design_time_builds(worktree, &settings, &env, pinned_sdk);
+            timeline.step("design-time builds");
+        }
+
+        // The Framework build is a managed OmniSharp.exe that has to be launched through Mono
+        let (command, mut args) = if !use_modern_net && platform != zed::Os::Windows && omnisharp_path.ends_with(".exe") {
+            let mono = worktree.which("mono").ok_or_else(|| {
+                "Legacy .NET Framework projects require Mono, but `mono` was not found on PATH. \
+                Install Mono (https://www.mono-project.com/download) or set \"useModernNet\": true."
+                    .to_string()
+            })?;
+            debug_log!(worktree, "[csharp_roslyn] Running OmniSharp through Mono: {mono}");
+            (mono, vec![omnisharp_path])
+        } else {
+            (omnisharp_path, Vec::new())
+        };
+        args.push("-lsp".to_string());
+        // Append user-provided server arguments (e.g. log level or MSBuild properties)
+        let server_args = get_server_args_from_settings(&settings);
+        if !server_args.is_empty() {
+            debug_log!(worktree, "[csharp_roslyn] Extra server arguments: {server_args:?}");
+            args.extend(server_args);
+        }
+
+        debug_log!(worktree, "[csharp_roslyn] Startup: {}", timeline.summary());
         debug_log!(worktree, "[csharp_roslyn] Starting OmniSharp with -lsp flag");
 
         Ok(zed::Command {
-            command: omnisharp_path,
-            args: vec!["-lsp".to_string()],
+            command,
+            args,
             env,
         })
     }
@@ -78,46 +152,65 @@ impl zed::Extension for CsharpRoslynExtension {
         _language_server_id: &LanguageServerId,
         worktree: &zed::Worktree,
     ) -> Result<Option<serde_json::Value>> {
-        // Check if this is a Unity project first
+        // Settings problems can't be shown to the user from here, so log them
+        let (settings, warnings) = RoslynSettings::load(worktree);
+        let _log = logging::scope(worktree, &settings);
+        if !warnings.is_empty() {
+            debug_log!(worktree, "[csharp_roslyn] Settings warnings:\n  {}", warnings.join("\n  "));
+        }
+
+        // Base initialization options with Roslyn extensions enabled
+        let mut init_options = json!({
+            "RoslynExtensionsOptions": {
+                "enableDecompilationSupport": true,
+                "enableImportCompletion": true,
+                "enableAnalyzersSupport": true
+            }
+        });
+
+        // External analyzer and refactoring assemblies
+        let analyzer_paths = get_analyzer_paths_from_settings(worktree, &settings);
+        if !analyzer_paths.is_empty() {
+            debug_log!(worktree, "[csharp_roslyn] Loading Roslyn extensions from: {analyzer_paths:?}");
+            init_options["RoslynExtensionsOptions"]["LocationPaths"] = json!(analyzer_paths);
+        }
+
+        if is_godot_project(worktree) {
+            debug_log!(worktree, "[csharp_roslyn] Godot project detected");
+            merge_json(&mut init_options, get_godot_omnisharp_config());
+        }
+
+        // Unity projects get Unity defaults (excluded folders, analyzers) and their generated solution
+        let mut unity_solution = None;
         if is_unity_project(worktree) {
             debug_log!(worktree, "[csharp_roslyn] Unity project detected");
-            
-            // Try to ensure Unity project files exist
-            match ensure_unity_project_files(worktree) {
+            merge_json(&mut init_options, get_unity_omnisharp_config());
+
+            match ensure_unity_project_files(worktree, &settings) {
                 Ok(solution_path) => {
                     debug_log!(worktree, "[csharp_roslyn] Unity solution found: {solution_path}");
-                    
-                    // Use Unity-specific config with solution
-                    let mut unity_config = get_unity_omnisharp_config();
-                    if let Some(solution_uri) = resolve_solution_uri(&solution_path, worktree) {
-                        debug_log!(worktree, "[csharp_roslyn] Resolved Unity solution URI: {solution_uri}");
-                        unity_config["solution"] = json!(solution_uri);
-                    }
-                    return Ok(Some(unity_config));
+                    unity_solution = Some(solution_path);
                 }
                 Err(instructions) => {
-                    debug_log!(worktree, "[csharp_roslyn] Unity project files not found");
-                    
-                    // Log Unity project files not found (no notification API available)
+                    // Log Unity project files not found (no notification API available);
+                    // OmniSharp will try to work anyway
                     debug_log!(worktree, "[csharp_roslyn] Unity project files not found: {instructions}");
-                    
-                    // Return Unity config without solution - OmniSharp will try to work anyway
-                    return Ok(Some(get_unity_omnisharp_config()));
                 }
             }
         }
-        
-        // Build base initialization options with Roslyn extensions for non-Unity projects
-        let mut init_options = json!({
-            "RoslynExtensionsOptions": {
-                "enableDecompilationSupport": true,
-                "enableImportCompletion": true,
-                "enableAnalyzersSupport": true
-            }
-        });
+
+        // Very large solutions trade some features for responsiveness
+        let solution_setting = get_solution_path_from_settings(&settings);
+        if is_large_solution(worktree, &settings, solution_setting.as_deref().or(unity_solution.as_deref())) {
+            debug_log!(worktree, "[csharp_roslyn] Using the large solution profile");
+            merge_json(&mut init_options, get_large_solution_omnisharp_config());
+        }
+
+        // Feature switches go last so they win over the project defaults above
+        merge_json(&mut init_options, get_feature_options_from_settings(worktree, &settings));
 
         // Try to get solution path from settings first
-        if let Some(solution_setting) = get_solution_path_from_settings(worktree) {
+        if let Some(solution_setting) = solution_setting {
             debug_log!(worktree, "[csharp_roslyn] Found solution in settings: {solution_setting}");
             if let Some(solution_uri) = resolve_solution_uri(&solution_setting, worktree) {
                 debug_log!(worktree, "[csharp_roslyn] Resolved solution URI: {solution_uri}");
@@ -126,8 +219,8 @@ impl zed::Extension for CsharpRoslynExtension {
             }
         }
 
-        // Fallback: try to auto-detect solution
-        if let Some(solution_path) = find_solution(worktree) {
+        // Fallback: the Unity-generated solution, or auto-detection
+        if let Some(solution_path) = unity_solution.or_else(|| find_solution(worktree)) {
             debug_log!(worktree, "[csharp_roslyn] Auto-detected solution: {solution_path}");
             if let Some(solution_uri) = resolve_solution_uri(&solution_path, worktree) {
                 debug_log!(worktree, "[csharp_roslyn] Resolved solution URI: {solution_uri}");
@@ -141,23 +234,71 @@ impl zed::Extension for CsharpRoslynExtension {
         Ok(Some(init_options))
     }
 
+    fn complete_slash_command_argument(
+        &self,
+        command: SlashCommand,
+        args: Vec<String>,
+    ) -> Result<Vec<SlashCommandArgumentCompletion>, String> {
+        match command.name.as_str() {
+            "dotnet-build" => Ok(Vec::new()),
+            "dotnet-new" => Ok(scaffolding::complete_template_argument(&args)),
+            "dotnet-sln" => Ok(scaffolding::complete_sln_argument(&args)),
+            "csharp-report" => Ok(Vec::new()),
+            "nuget" => nuget::complete_nuget_argument(&args),
+            name => Err(format!("Unknown slash command: {name}")),
+        }
+    }
+
+    fn run_slash_command(
+        &self,
+        command: SlashCommand,
+        args: Vec<String>,
+        worktree: Option<&zed::Worktree>,
+    ) -> Result<SlashCommandOutput, String> {
+        match command.name.as_str() {
+            "dotnet-build" => msbuild::run_dotnet_build(&args, worktree),
+            "dotnet-new" => scaffolding::run_dotnet_new(&args, worktree),
+            "dotnet-sln" => scaffolding::run_dotnet_sln(&args, worktree),
+            "csharp-report" => support::run_csharp_report(worktree),
+            "nuget" => nuget::run_nuget_command(&args, worktree),
+            name => Err(format!("Unknown slash command: {name}")),
+        }
+    }
+
     fn get_dap_binary(
         &mut self,
         adapter_name: String,
         config: DebugTaskDefinition,
-        _user_provided_debug_adapter_path: Option<String>,
+        user_provided_debug_adapter_path: Option<String>,
         worktree: &zed::Worktree,
     ) -> Result<DebugAdapterBinary, String> {
+        let settings = RoslynSettings::for_worktree(worktree);
+        let _log = logging::scope(worktree, &settings);
+        if adapter_name == "unity" {
+            return unity_debug_adapter_binary(&config, user_provided_debug_adapter_path, worktree, &settings);
+        }
         if adapter_name != "netcoredbg" {
             return Err(format!("Unknown debug adapter: {}", adapter_name));
         }
 
-        let command = debugger::ensure_debugger(worktree)?;
-
         // Parse the config JSON to get the request type
         let mut config_json: serde_json::Value = serde_json::from_str(&config.config)
             .map_err(|e| format!("Failed to parse config: {}", e))?;
 
+        // Container launches become an attach over `docker exec` once the container is running
+        container::prepare_container_launch(&mut config_json, worktree, &settings)?;
+
+        // Remote targets run netcoredbg on the other end of the pipe; only local sessions need a download
+        let remote_command = debugger::pipe_transport_command(&config_json)?;
+        let is_remote = remote_command.is_some();
+        let command = match remote_command {
+            Some(command) => {
+                debug_log!(worktree, "[csharp_roslyn] Debugging over pipe transport: {} {:?}", command.command, command.args);
+                command
+            }
+            None => debugger::ensure_debug_adapter(worktree, &settings, user_provided_debug_adapter_path)?,
+        };
+
         let request_type = config_json
             .get("request")
             .and_then(|v| v.as_str())
@@ -168,9 +309,78 @@ impl zed::Extension for CsharpRoslynExtension {
             _ => StartDebuggingRequestArgumentsRequest::Launch,
         };
 
+        // Test debugging: the test task was started with VSTEST_HOST_DEBUG=1, so the test host
+        // prints its PID and blocks until a debugger attaches. Find it and attach to it.
+        if config_json.get("waitForTestHost").and_then(|v| v.as_bool()) == Some(true) && !is_remote {
+            let test_dir = config_json.get("testDirectory").and_then(|v| v.as_str()).map(|dir| dir.to_string());
+            let pid = processes::wait_for_test_host(worktree, test_dir.as_deref())?;
+            if let Some(obj) = config_json.as_object_mut() {
+                obj.remove("waitForTestHost");
+                obj.remove("testDirectory");
+                obj.insert("processId".to_string(), json!(pid));
+            }
+        }
+
+        // netcoredbg attaches by numeric PID only; resolve `processName` and string ids here
+        if request == StartDebuggingRequestArgumentsRequest::Attach {
+            let pid = match config_json.get("processId") {
+                Some(serde_json::Value::Number(pid)) => Some(
+                    pid.as_u64()
+                        .and_then(|pid| u32::try_from(pid).ok())
+                        .ok_or_else(|| format!("Invalid processId: {pid}"))?,
+                ),
+                Some(serde_json::Value::String(pid)) => Some(
+                    pid.trim()
+                        .parse::<u32>()
+                        .map_err(|_| format!("Invalid processId: {pid}"))?,
+                ),
+                _ => None,
+            };
+            // `project` attaches to the process running that project's assembly, e.g. a service
+            let process_name = match (
+                config_json.get("processName").and_then(|v| v.as_str()),
+                config_json.get("project").and_then(|v| v.as_str()),
+            ) {
+                (Some(name), _) => Some(name.to_string()),
+                (None, Some(project)) => Some(project_assembly_name(project, worktree)?),
+                (None, None) => None,
+            };
+            let pid = match (pid, process_name) {
+                (Some(pid), _) => pid,
+                (None, Some(_)) if is_remote => {
+                    return Err("processName can't be resolved on a remote target; set processId".to_string())
+                }
+                (None, Some(name)) => processes::find_process_id(&name, worktree)?,
+                (None, None) => {
+                    return Err("Attach configurations need a processId, processName, or project".to_string())
+                }
+            };
+            if let Some(obj) = config_json.as_object_mut() {
+                obj.remove("processName");
+                obj.remove("project");
+                obj.insert("processId".to_string(), json!(pid));
+            }
+        }
+
+        // Godot projects run inside the Godot editor/player, which hosts the .NET runtime
+        if config_json.get("program").and_then(|v| v.as_str()) == Some("$GODOT") {
+            let godot = find_godot_executable(&settings, worktree)?;
+            debug_log!(worktree, "[csharp_roslyn] Launching Godot: {godot}");
+            if let Some(obj) = config_json.as_object_mut() {
+                obj.insert("program".to_string(), json!(godot));
+                obj.entry("args").or_insert(json!(["--path", worktree.root_path()]));
+                obj.entry("cwd").or_insert(json!(worktree.root_path()));
+            }
+        }
+
         // If the configuration contains a program path with $TARGET_FRAMEWORK placeholder,
         // resolve it by reading the corresponding .csproj file to get the actual target framework.
         // Note: Zed has already expanded $ZED_WORKTREE_ROOT to the full path at this point.
+        let mut located_project = None;
+        let requested_framework = config_json
+            .get("targetFramework")
+            .and_then(|v| v.as_str())
+            .map(|tf| tf.to_string());
         if let Some(program_value) = config_json.get_mut("program") {
             if let Some(program_str) = program_value.as_str() {
                 // Check if the path contains our $TARGET_FRAMEWORK placeholder
@@ -202,10 +412,36 @@ impl zed::Extension for CsharpRoslynExtension {
                                 };
 
                                 if let Ok(text) = worktree.read_text_file(&csproj_path) {
-                                    let proj = DotNetProject::from_csproj_text(&text, std::path::Path::new(&csproj_path));
+                                    let project_text = with_directory_build_props(worktree, &csproj_path, &text);
+                                    let proj = DotNetProject::from_csproj_text(&project_text, std::path::Path::new(&csproj_path));
                                     
-                                    // Replace $TARGET_FRAMEWORK with the actual value
-                                    let new_program = program_str.replace("$TARGET_FRAMEWORK", &proj.target_framework);
+                                    // Replace $TARGET_FRAMEWORK with the chosen framework
+                                    let target_framework = select_target_framework(&proj, requested_framework.as_deref(), &settings)?;
+                                    // Compute the real output path so custom OutputPath, RuntimeIdentifier,
+                                    // and artifacts layouts resolve; otherwise just fill in the framework.
+                                    // MSBuild's own TargetPath is preferred when it can be evaluated.
+                                    let new_program = if program_str.starts_with(&worktree_root) {
+                                        let configuration = parts.get(bin_idx + 1).copied().unwrap_or("Debug");
+                                        let project_file = PathBuf::from(&worktree_root).join(&csproj_path);
+                                        let evaluated = msbuild::evaluate_project(
+                                            worktree,
+                                            &settings,
+                                            &project_file.to_string_lossy(),
+                                            &project_text,
+                                            configuration,
+                                            &target_framework,
+                                        );
+                                        match evaluated.as_ref().and_then(|project| project.get("TargetPath")) {
+                                            Some(target_path) => target_path.to_string(),
+                                            None => PathBuf::from(&worktree_root)
+                                                .join(proj.get_output_path(configuration, &target_framework))
+                                                .to_string_lossy()
+                                                .to_string(),
+                                        }
+                                    } else {
+                                        program_str.replace("$TARGET_FRAMEWORK", &target_framework)
+                                    };
+                                    located_project = Some((project_dir, name.to_string(), text));
                                     *program_value = serde_json::Value::String(new_program);
                                 }
                             }
@@ -215,6 +451,24 @@ impl zed::Extension for CsharpRoslynExtension {
             }
         }
 
+        debugger::apply_debug_option_defaults(&mut config_json, debugger::get_debug_option_defaults(&settings).as_ref());
+        debugger::apply_env_file(&mut config_json, worktree)?;
+
+        // ASP.NET Core and worker services: launching the DLL directly skips what `dotnet run` would apply
+        if let Some((project_dir, project_name, csproj_text)) = located_project {
+            if user_secrets_id(&csproj_text).is_some() {
+                let project_file = PathBuf::from(worktree.root_path())
+                    .join(&project_dir)
+                    .join(format!("{project_name}.csproj"));
+                apply_user_secrets(&mut config_json, &project_file.to_string_lossy(), worktree);
+            }
+            if is_web_project(&csproj_text) {
+                apply_web_launch_defaults(&mut config_json, &project_dir, &project_name, worktree);
+            } else if is_worker_project(&csproj_text) {
+                apply_worker_launch_defaults(&mut config_json, &project_dir, &project_name, worktree);
+            }
+        }
+
         Ok(DebugAdapterBinary {
             command: Some(command.command),
             arguments: command.args,
@@ -246,6 +500,18 @@ impl zed::Extension for CsharpRoslynExtension {
     }
 
     fn dap_config_to_scenario(&mut self, config: DebugConfig) -> Result<DebugScenario, String> {
+        // The Unity debugger only attaches to the editor; the path to EditorInstance.json is
+        // filled in when the session starts
+        if config.adapter == "unity" {
+            return Ok(DebugScenario {
+                label: config.label,
+                adapter: config.adapter,
+                build: None,
+                config: json!({ "request": "launch", "name": "Unity Editor" }).to_string(),
+                tcp_connection: None,
+            });
+        }
+
         // Extract launch request details
         let (program, args, cwd, envs) = match &config.request {
             DebugRequest::Launch(launch) => (
@@ -254,8 +520,22 @@ impl zed::Extension for CsharpRoslynExtension {
                 launch.cwd.clone().unwrap_or_else(|| ".".to_string()),
                 launch.envs.clone(),
             ),
-            DebugRequest::Attach(_) => {
-                return Err("Attach requests not yet supported".to_string());
+            DebugRequest::Attach(attach) => {
+                let process_id = attach
+                    .process_id
+                    .ok_or_else(|| "Attach requests need a process id".to_string())?;
+                let attach_config = json!({
+                    "request": "attach",
+                    "processId": process_id,
+                });
+
+                return Ok(DebugScenario {
+                    label: config.label,
+                    adapter: config.adapter,
+                    build: None,
+                    config: attach_config.to_string(),
+                    tcp_connection: None,
+                });
             }
         };
 
@@ -289,6 +569,14 @@ impl zed::Extension for CsharpRoslynExtension {
             return None;
         }
 
+        if is_test_task(&build_task) {
+            return Some(test_host_scenario(debug_adapter_name, resolved_label, &build_task));
+        }
+
+        if is_godot_task(&build_task) {
+            return Some(godot_scenario(debug_adapter_name, resolved_label, &build_task));
+        }
+
         // Only create debug scenarios for "run" related tasks
         // Check if this is a dotnet run/watch command
         let is_run_task = build_task.command.contains("dotnet")
@@ -306,39 +594,60 @@ impl zed::Extension for CsharpRoslynExtension {
         // For .NET debugging, we need to know the exact DLL path.
         // Try to infer it from the build task or use a generic path.
 
-        // Try to infer project path and name from build task
-        let program = if let Some(project_arg) =
-            build_task.args.iter().find(|arg| arg.ends_with(".csproj"))
-        {
+        // Try to infer project path and name from build task: a .csproj argument, or the
+        // directory passed with `--project` (as the entry point run tasks do)
+        let project_arg = build_task
+            .args
+            .iter()
+            .find(|arg| arg.ends_with(".csproj"))
+            .or_else(|| {
+                let idx = build_task.args.iter().position(|arg| arg == "--project")?;
+                build_task.args.get(idx + 1)
+            });
+        let program = if let Some(project_arg) = project_arg {
             // Extract project directory and name from .csproj path
             // e.g., "src/ConsoleApp/ConsoleApp.csproj" -> directory="src/ConsoleApp", name="ConsoleApp"
-
-            // Get the parent directory path (everything before the .csproj filename)
-            let project_dir = if let Some(last_slash) = project_arg.rfind(['/', '\\']) {
-                &project_arg[..last_slash]
-            } else {
-                "."
+            // A project directory is named after its project: "src/ConsoleApp" -> name="ConsoleApp"
+            let project_arg = project_arg.trim_end_matches(['/', '\\']);
+            let (project_dir, project_name) = match project_arg.strip_suffix(".csproj") {
+                Some(without_extension) => {
+                    // Get the parent directory path (everything before the .csproj filename)
+                    let project_dir = match project_arg.rfind(['/', '\\']) {
+                        Some(last_slash) => &project_arg[..last_slash],
+                        None => ".",
+                    };
+                    let project_name = without_extension.rsplit(['/', '\\']).next().unwrap_or("app");
+                    (project_dir, project_name)
+                }
+                None => (project_arg, project_arg.rsplit(['/', '\\']).next().unwrap_or("app")),
             };
 
-            // Get the project name from the .csproj filename
-            let project_name = project_arg
-                .split(['/', '\\'])
-                .last()
-                .and_then(|s| s.strip_suffix(".csproj"))
-                .unwrap_or("app");
-
             // Use forward slashes in the path template - Zed will normalize when expanding $ZED_WORKTREE_ROOT
             // NOTE: The target framework placeholder will be resolved by get_dap_binary at debug time
-            format!(
-                "$ZED_WORKTREE_ROOT/{}/bin/Debug/$TARGET_FRAMEWORK/{}.dll",
-                project_dir.replace('\\', "/"),
-                project_name
-            )
+            let project_dir = project_dir.replace('\\', "/");
+            if project_dir.starts_with('/') || project_dir.chars().nth(1) == Some(':') {
+                format!("{project_dir}/bin/Debug/$TARGET_FRAMEWORK/{project_name}.dll")
+            } else {
+                format!("$ZED_WORKTREE_ROOT/{project_dir}/bin/Debug/$TARGET_FRAMEWORK/{project_name}.dll")
+            }
         } else {
             // Fallback to a generic path (will be resolved by get_dap_binary)
             "$ZED_WORKTREE_ROOT/bin/Debug/$TARGET_FRAMEWORK/app.dll".to_string()
         };
 
+        // A task for a multi-targeted project picks its framework with `-f`/`--framework`
+        let framework = build_task
+            .args
+            .iter()
+            .position(|arg| arg == "-f" || arg == "--framework")
+            .and_then(|idx| build_task.args.get(idx + 1))
+            .cloned();
+
+        // Mobile targets run on Mono or a device; netcoredbg can't debug them
+        if framework.as_deref().is_some_and(is_mobile_target_framework) {
+            return None;
+        }
+
         let mut config = json!({
             "request": "launch",
             "program": program,
@@ -347,36 +656,485 @@ impl zed::Extension for CsharpRoslynExtension {
             "stopAtEntry": false,
             "console": "internalConsole"
         });
+        if let (Some(framework), Some(obj)) = (&framework, config.as_object_mut()) {
+            obj.insert("targetFramework".to_string(), json!(framework));
+        }
 
         // Ensure request field exists (required by DAP)
         if let Some(obj) = config.as_object_mut() {
             obj.entry("request").or_insert("launch".into());
         }
 
+        // Build before launching so the debugger never runs a stale or missing DLL.
+        // If the build fails, Zed aborts the session and shows the build task's output.
+        let build = debug_build_task(project_arg.map(|arg| arg.as_str()), framework.as_deref(), &build_task);
+
         Some(DebugScenario {
             adapter: debug_adapter_name,
             label: resolved_label,
             config: config.to_string(),
             tcp_connection: None,
-            build: None,
+            build: Some(build),
         })
     }
 }
 
+/// `dotnet build <project> -c Debug [-f <tfm>]` step run before launching a located scenario.
+/// It reuses the run task's cwd and env so the project path resolves the same way.
+fn debug_build_task(project: Option<&str>, framework: Option<&str>, run_task: &TaskTemplate) -> BuildTaskDefinition {
+    let mut args = vec!["build".to_string()];
+    args.extend(project.map(|project| project.to_string()));
+    args.extend(["-c".to_string(), "Debug".to_string()]);
+    if let Some(framework) = framework {
+        args.extend(["-f".to_string(), framework.to_string()]);
+    }
+
+    BuildTaskDefinition::Template(BuildTaskDefinitionTemplatePayload {
+        locator_name: None,
+        template: BuildTaskTemplate {
+            label: format!("dotnet {}", args.join(" ")),
+            command: "dotnet".to_string(),
+            args,
+            env: run_task.env.clone(),
+            cwd: run_task.cwd.clone(),
+        },
+    })
+}
+
+/// Choose the framework to debug for a (possibly multi-targeted) project: the debug configuration's
+/// `targetFramework`, then the `debugTargetFramework` setting if the project targets it, then the first one.
+fn select_target_framework(project: &DotNetProject, requested: Option<&str>, settings: &RoslynSettings) -> Result<String> {
+    if let Some(requested) = requested {
+        if is_mobile_target_framework(requested) {
+            return Err(format!(
+                "{requested} is a mobile target, which netcoredbg can't debug. \
+                Pick a desktop framework, or debug on the device with the platform's tooling."
+            ));
+        }
+        return Ok(requested.to_string());
+    }
+
+    let debuggable = project.debuggable_target_frameworks();
+    let preferred = settings.debug_target_framework.clone();
+    match (preferred, debuggable.first()) {
+        (Some(tf), _) if debuggable.contains(&tf.as_str()) => Ok(tf),
+        _ if debuggable.contains(&project.target_framework.as_str()) => Ok(project.target_framework.clone()),
+        (_, Some(tf)) => Ok(tf.to_string()),
+        (_, None) => Err(format!(
+            "{} only targets mobile platforms ({}), which netcoredbg can't debug. \
+            Add a desktop target such as net8.0-windows10.0.19041.0, or debug on the device with the platform's tooling.",
+            project.assembly_name,
+            project.target_frameworks.join(", ")
+        )),
+    }
+}
+
+/// Apply the `dotnet run` defaults for an ASP.NET Core project to a launch configuration:
+/// the project directory as content root, the `Project` launch profile's URLs and environment,
+/// and `ASPNETCORE_ENVIRONMENT=Development`. Values already in the configuration win.
+fn apply_web_launch_defaults(
+    config_json: &mut serde_json::Value,
+    project_dir: &str,
+    project_name: &str,
+    worktree: &zed::Worktree,
+) {
+    let profile = apply_hosting_defaults(config_json, project_dir, project_name, "ASPNETCORE_ENVIRONMENT", worktree);
+    debug_log!(worktree, "[csharp_roslyn] ASP.NET Core launch profile: {profile:?}");
+
+    if let (Some(urls), Some(env)) = (&profile.application_url, config_json.get_mut("env").and_then(|env| env.as_object_mut())) {
+        env.entry("ASPNETCORE_URLS").or_insert(json!(urls));
+    }
+
+    // Kestrel refuses to start HTTPS endpoints without a development certificate
+    let uses_https = profile.application_url.is_some_and(|urls| urls.contains("https://"));
+    if uses_https && !has_https_dev_cert(worktree) {
+        debug_log!(
+            worktree,
+            "[csharp_roslyn] No trusted HTTPS development certificate found; run `dotnet dev-certs https --trust`"
+        );
+    }
+}
+
+/// Worker services (`Microsoft.NET.Sdk.Worker`) have no URLs to bind; they only need the
+/// environment and content root `dotnet run` would give them
+fn apply_worker_launch_defaults(
+    config_json: &mut serde_json::Value,
+    project_dir: &str,
+    project_name: &str,
+    worktree: &zed::Worktree,
+) {
+    let profile = apply_hosting_defaults(config_json, project_dir, project_name, "DOTNET_ENVIRONMENT", worktree);
+    debug_log!(worktree, "[csharp_roslyn] Worker service launch profile: {profile:?}");
+    if let Some(obj) = config_json.as_object_mut() {
+        obj.entry("console").or_insert(json!("internalConsole"));
+    }
+}
+
+/// Defaults shared by Generic Host apps: the project directory as content root (appsettings.json
+/// is resolved against it), the launch profile's environment, and `environment_variable` set to
+/// Development. Returns the launch profile that was applied.
+fn apply_hosting_defaults(
+    config_json: &mut serde_json::Value,
+    project_dir: &str,
+    project_name: &str,
+    environment_variable: &str,
+    worktree: &zed::Worktree,
+) -> LaunchProfile {
+    let worktree_root = worktree.root_path();
+    let launch_settings_path = if project_dir == "." {
+        "Properties/launchSettings.json".to_string()
+    } else {
+        format!("{project_dir}/Properties/launchSettings.json")
+    };
+    let profile = worktree
+        .read_text_file(&launch_settings_path)
+        .ok()
+        .and_then(|text| parse_launch_settings(&text, project_name))
+        .unwrap_or_default();
+
+    let Some(obj) = config_json.as_object_mut() else {
+        return profile;
+    };
+
+    let cwd = obj.get("cwd").and_then(|v| v.as_str()).unwrap_or_default();
+    if cwd.is_empty() || cwd.trim_end_matches(['/', '\\']) == worktree_root.trim_end_matches(['/', '\\']) {
+        let project_cwd = PathBuf::from(&worktree_root).join(project_dir);
+        obj.insert("cwd".to_string(), json!(project_cwd.to_string_lossy()));
+    }
+
+    let env = obj.entry("env").or_insert_with(|| json!({}));
+    if !env.is_object() {
+        *env = json!({});
+    }
+    if let Some(env) = env.as_object_mut() {
+        for (key, value) in &profile.environment_variables {
+            env.entry(key.clone()).or_insert(json!(value));
+        }
+        env.entry(environment_variable).or_insert(json!("Development"));
+    }
+    profile
+}
+
+/// Assembly name of a worktree project, used to find its running process
+fn project_assembly_name(project: &str, worktree: &zed::Worktree) -> Result<String> {
+    let relative = worktree_relative_path(project, &worktree.root_path())
+        .ok_or_else(|| format!("Project {project} is outside the worktree"))?;
+    let text = worktree
+        .read_text_file(&relative)
+        .map_err(|e| format!("Failed to read {relative}: {e}"))?;
+    let text = with_directory_build_props(worktree, &relative, &text);
+    Ok(DotNetProject::from_csproj_text(&text, Path::new(&relative)).assembly_name)
+}
+
+/// Pass the project's user secrets to the program as environment variables, so they're available
+/// even when it doesn't run in the Development environment or reads plain environment variables.
+/// Values set in the configuration win.
+fn apply_user_secrets(config_json: &mut serde_json::Value, project_file: &str, worktree: &zed::Worktree) {
+    if worktree.which("dotnet").is_none() {
+        return;
+    }
+    let output = match processes::command_on_path(worktree, "dotnet")
+        .args(["user-secrets", "list", "--project", project_file])
+        .output()
+    {
+        Ok(output) if output.status == Some(0) => output,
+        _ => {
+            debug_log!(worktree, "[csharp_roslyn] Could not read user secrets for {project_file}");
+            return;
+        }
+    };
+    let secrets = parse_user_secrets_list(&String::from_utf8_lossy(&output.stdout));
+    debug_log!(worktree, "[csharp_roslyn] Applying {} user secret(s) from {project_file}", secrets.len());
+
+    let Some(obj) = config_json.as_object_mut() else {
+        return;
+    };
+    let env = obj.entry("env").or_insert_with(|| json!({}));
+    if !env.is_object() {
+        *env = json!({});
+    }
+    if let Some(env) = env.as_object_mut() {
+        for (key, value) in secrets {
+            env.entry(key).or_insert(json!(value));
+        }
+    }
+}
+
+/// Check for an ASP.NET Core HTTPS development certificate (`dotnet dev-certs https --check`)
+fn has_https_dev_cert(worktree: &zed::Worktree) -> bool {
+    if worktree.which("dotnet").is_none() {
+        return true;
+    }
+    processes::command_on_path(worktree, "dotnet")
+        .args(["dev-certs", "https", "--check"])
+        .output()
+        .map(|output| output.status == Some(0))
+        .unwrap_or(true)
+}
+
+/// Check whether a task runs `dotnet test`
+fn is_test_task(task: &TaskTemplate) -> bool {
+    let words: Vec<&str> = task
+        .command
+        .split_whitespace()
+        .chain(task.args.iter().map(|arg| arg.as_str()))
+        .collect();
+    words.iter().any(|word| word.ends_with("dotnet") || word.ends_with("dotnet.exe")) && words.contains(&"test")
+}
+
+/// Attach scenario for a `dotnet test` task. The task must run with `VSTEST_HOST_DEBUG=1`;
+/// `get_dap_binary` then waits for a new test host started from the task's directory and fills
+/// in its process id.
+fn test_host_scenario(debug_adapter_name: String, resolved_label: String, task: &TaskTemplate) -> DebugScenario {
+    let config = json!({
+        "request": "attach",
+        "waitForTestHost": true,
+        "testDirectory": task.cwd.clone().unwrap_or_else(|| "$ZED_WORKTREE_ROOT".to_string())
+    });
+
+    DebugScenario {
+        adapter: debug_adapter_name,
+        label: resolved_label,
+        config: config.to_string(),
+        tcp_connection: None,
+        build: None,
+    }
+}
+
+/// Whether a task runs the Godot editor/player (`godot`, `godot4`, `Godot_v4.2-stable_mono_linux.x86_64`, ...)
+fn is_godot_task(task: &TaskTemplate) -> bool {
+    task.command
+        .rsplit(['/', '\\'])
+        .next()
+        .is_some_and(|name| name.to_lowercase().starts_with("godot"))
+}
+
+/// Launch the task's Godot binary under netcoredbg, building the C# project first
+fn godot_scenario(debug_adapter_name: String, resolved_label: String, task: &TaskTemplate) -> DebugScenario {
+    let args = if task.args.is_empty() {
+        vec!["--path".to_string(), "$ZED_WORKTREE_ROOT".to_string()]
+    } else {
+        task.args.clone()
+    };
+    let config = json!({
+        "request": "launch",
+        "program": task.command,
+        "args": args,
+        "cwd": task.cwd.clone().unwrap_or_else(|| "$ZED_WORKTREE_ROOT".to_string()),
+        "env": task.env,
+        "stopAtEntry": false,
+        "console": "internalConsole"
+    });
+
+    DebugScenario {
+        adapter: debug_adapter_name,
+        label: resolved_label,
+        config: config.to_string(),
+        tcp_connection: None,
+        build: Some(debug_build_task(None, None, task)),
+    }
+}
+
+/// Find the Godot executable: the `godotPath` setting, then `$GODOT`, then `godot`/`godot4` on PATH
+fn find_godot_executable(settings: &RoslynSettings, worktree: &zed::Worktree) -> Result<String> {
+    if let Some(path) = &settings.godot_path {
+        return Ok(path.clone());
+    }
+
+    let from_env = worktree
+        .shell_env()
+        .into_iter()
+        .find(|(key, value)| key == "GODOT" && !value.is_empty())
+        .map(|(_, value)| value);
+    from_env
+        .or_else(|| worktree.which("godot"))
+        .or_else(|| worktree.which("godot4"))
+        .ok_or_else(|| "Godot was not found; set \"godotPath\" or the GODOT environment variable".to_string())
+}
+
+/// Debug adapter binary for the `unity` adapter, attaching to the running Unity Editor
+fn unity_debug_adapter_binary(
+    config: &DebugTaskDefinition,
+    user_provided_debug_adapter_path: Option<String>,
+    worktree: &zed::Worktree,
+    settings: &RoslynSettings,
+) -> Result<DebugAdapterBinary> {
+    let mut config_json: serde_json::Value = serde_json::from_str(&config.config)
+        .map_err(|e| format!("Failed to parse config: {}", e))?;
+    unity_debugger::apply_editor_attach_defaults(&mut config_json, worktree)?;
+    let command = unity_debugger::ensure_unity_debug_adapter(worktree, settings, user_provided_debug_adapter_path)?;
+
+    Ok(DebugAdapterBinary {
+        command: Some(command.command),
+        arguments: command.args,
+        envs: command.env,
+        cwd: Some(worktree.root_path()),
+        connection: None,
+        request_args: StartDebuggingRequestArguments {
+            configuration: serde_json::to_string(&config_json).map_err(|e| format!("Failed to serialize modified config: {e}"))?,
+            request: StartDebuggingRequestArgumentsRequest::Launch,
+        },
+    })
+}
+
 /// Read solution path from user settings
-fn get_solution_path_from_settings(worktree: &zed::Worktree) -> Option<String> {
-    let settings = LspSettings::for_worktree("omnisharp-roslyn", worktree).ok()?;
-
-    // Try to get solution_path from settings
-    if let Some(init_options) = settings.initialization_options {
-        if let Some(solution) = init_options.get("solution") {
-            if let Some(solution_str) = solution.as_str() {
-                return Some(solution_str.to_string());
+fn get_solution_path_from_settings(settings: &RoslynSettings) -> Option<String> {
+    settings.solution.clone()
+}
+
+/// Run design-time builds for the solution projects whose sources are generated at build time:
+/// WPF/WinUI XAML code-behind and Grpc.Tools `.proto` classes. Proto contents are part of the
+/// build's inputs, so edited `.proto` files are regenerated the next time the server starts.
+///
+/// Builds use the SDK pinned by `global.json`, or else the first installation with an SDK. When
+/// there is none (e.g. only the private runtime), the builds are skipped.
+fn run_design_time_builds(
+    worktree: &zed::Worktree,
+    settings: &RoslynSettings,
+    env: &[(String, String)],
+    pinned_sdk: Option<dotnet_sdk::DotnetInstallation>,
+) {
+    let root_path = worktree.root_path();
+    let Some(solution) = get_solution_path_from_settings(settings)
+        .or_else(|| find_solution(worktree))
+        .and_then(|solution| worktree_relative_path(&solution, &root_path))
+    else {
+        return;
+    };
+    let mut builds = Vec::new();
+    for project_path in solution_project_paths(worktree, &solution) {
+        let Ok(text) = worktree.read_text_file(&project_path) else {
+            continue;
+        };
+        let text = with_directory_build_props(worktree, &project_path, &text);
+        let mut proto_inputs = String::new();
+        if msbuild::is_grpc_project(&text) {
+            let project_dir = Path::new(&project_path).parent().unwrap_or(Path::new(""));
+            for proto in msbuild::protobuf_files(&text) {
+                let proto_path = project_dir.join(&proto).to_string_lossy().replace('\\', "/");
+                proto_inputs.push_str(&worktree.read_text_file(&proto_path).unwrap_or_default());
             }
+        } else if !msbuild::is_xaml_project(&text) {
+            continue;
         }
+        let project_file = PathBuf::from(&root_path).join(&project_path);
+        builds.push((project_file.to_string_lossy().to_string(), text, proto_inputs));
+    }
+    if builds.is_empty() {
+        return;
     }
 
-    None
+    let Some(sdk) = pinned_sdk
+        .filter(|install| install.root.is_some())
+        .or_else(|| dotnet_sdk::find_sdk_installation(worktree, env))
+    else {
+        debug_log!(worktree, "[csharp_roslyn] Skipping design-time builds: no .NET SDK is installed");
+        return;
+    };
+    let sdk_env = sdk.command_env(env);
+    for (project_file, text, proto_inputs) in builds {
+        msbuild::run_design_time_build(worktree, settings, &sdk_env, &project_file, &text, &proto_inputs);
+    }
+}
+
+/// Whether to use the large solution profile: the `largeSolutionMode` setting, or else whether
+/// the solution has more than `LARGE_SOLUTION_PROJECT_COUNT` projects
+fn is_large_solution(worktree: &zed::Worktree, settings: &RoslynSettings, solution: Option<&str>) -> bool {
+    if let Some(enabled) = settings.large_solution_mode {
+        return enabled;
+    }
+    let Some(solution) = solution.and_then(|solution| worktree_relative_path(solution, &worktree.root_path())) else {
+        return false;
+    };
+    let project_count = solution_project_paths(worktree, &solution).len();
+    debug_log!(worktree, "[csharp_roslyn] {solution} has {project_count} projects");
+    project_count > LARGE_SOLUTION_PROJECT_COUNT
+}
+
+/// Decide between the .NET 6 build of OmniSharp and the .NET Framework/Mono build.
+/// An explicit `useModernNet` setting wins; otherwise the configured solution is
+/// scanned for old-style (non-SDK) projects.
+fn use_modern_net(worktree: &zed::Worktree, settings: &RoslynSettings) -> bool {
+    if let Some(use_modern_net) = settings.use_modern_net {
+        return use_modern_net;
+    }
+
+    let Some(solution) = get_solution_path_from_settings(settings)
+        .and_then(|solution| worktree_relative_path(&solution, &worktree.root_path()))
+    else {
+        return true;
+    };
+    !solution_has_legacy_projects(worktree, &solution)
+}
+
+/// Convert a path or file:// URI from settings into a path relative to the worktree root
+fn worktree_relative_path(value: &str, root_path: &str) -> Option<String> {
+    let path = match Url::parse(value) {
+        Ok(url) if url.scheme() == "file" => url.to_file_path().ok()?,
+        _ => PathBuf::from(value),
+    };
+    if path.is_relative() {
+        return Some(path.to_string_lossy().replace('\\', "/"));
+    }
+    path.strip_prefix(root_path)
+        .ok()
+        .map(|relative| relative.to_string_lossy().replace('\\', "/"))
+}
+
+/// Read `analyzerPaths`: directories or assemblies with Roslyn analyzers and refactorings, passed
+/// to OmniSharp as `RoslynExtensionsOptions.LocationPaths`. Relative paths are resolved against
+/// the worktree root; empty entries are skipped with a log message.
+fn get_analyzer_paths_from_settings(worktree: &zed::Worktree, settings: &RoslynSettings) -> Vec<String> {
+    let root_path = worktree.root_path();
+    let mut resolved = Vec::new();
+    for path in &settings.analyzer_paths {
+        let path = path.trim();
+        if path.is_empty() {
+            debug_log!(worktree, "[csharp_roslyn] Ignoring empty analyzerPaths entry");
+            continue;
+        }
+        let path = PathBuf::from(path);
+        let path = if path.is_relative() { PathBuf::from(&root_path).join(path) } else { path };
+        let path = path.to_string_lossy().to_string();
+        if !resolved.contains(&path) {
+            resolved.push(path);
+        }
+    }
+    resolved
+}
+
+/// OmniSharp options for the `features` switches, e.g. `"features": { "analyzers": false }`
+fn get_feature_options_from_settings(worktree: &zed::Worktree, settings: &RoslynSettings) -> serde_json::Value {
+    let mut options = json!({});
+    for (feature, &enabled) in &settings.features {
+        let overlay = match feature.as_str() {
+            "analyzers" => json!({ "RoslynExtensionsOptions": { "enableAnalyzersSupport": enabled } }),
+            "importCompletion" => json!({ "RoslynExtensionsOptions": { "enableImportCompletion": enabled } }),
+            "decompilation" => json!({ "RoslynExtensionsOptions": { "enableDecompilationSupport": enabled } }),
+            "inlayHints" => json!({
+                "RoslynExtensionsOptions": {
+                    "InlayHintsOptions": {
+                        "EnableForParameters": enabled,
+                        "EnableForTypes": enabled
+                    }
+                }
+            }),
+            _ => {
+                debug_log!(
+                    worktree,
+                    "[csharp_roslyn] features.{feature} has no OmniSharp option; turn it off in Zed's settings instead"
+                );
+                continue;
+            }
+        };
+        merge_json(&mut options, overlay);
+    }
+    options
+}
+
+/// Read extra server command-line arguments from user settings
+fn get_server_args_from_settings(settings: &RoslynSettings) -> Vec<String> {
+    settings.server_args.clone()
 }
 
 /// Attempt to detect a solution file in the worktree root.
@@ -427,3 +1185,15 @@ fn resolve_solution_uri(value: &str, worktree: &zed::Worktree) -> Option<String>
 
     Some(path_to_uri(&candidate))
 }
+
+/// Deep-merge `overlay` into `base`: objects merge key by key, anything else replaces
+fn merge_json(base: &mut serde_json::Value, overlay: serde_json::Value) {
+    match (base, overlay) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                merge_json(base.entry(key).or_insert(serde_json::Value::Null), value);
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
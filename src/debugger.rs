@@ -1,17 +1,145 @@
 use std::path::{Path, PathBuf};
+use serde::Deserialize;
 use zed_extension_api::{self as zed, Command, Result, Worktree};
 
+use crate::download::{
+    download_verified, expected_checksum, resolve_local_binary, verify_binary, CacheLock, GithubAsset,
+    LocalPackage,
+};
 use crate::logging::debug_log;
+use crate::settings::RoslynSettings;
 
-const NETCOREDBG_VERSION: &str = "v3.1.2-1054";
+pub(crate) const NETCOREDBG_VERSION: &str = "v3.1.2-1054";
 const NETCOREDBG_REPO: &str = "https://github.com/marcptrs/netcoredbg";
+const SOURCE_MARKER_FILE: &str = "source.txt";
 
-pub fn ensure_debugger(worktree: &Worktree) -> Result<Command> {
-    let cache_dir = get_debugger_cache_dir()?;
+/// Debug adapter backing the `netcoredbg` adapter name, selected by the `debugAdapter` setting
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DebugAdapterKind {
+    /// Samsung's open-source netcoredbg, downloaded automatically
+    Netcoredbg,
+    /// Microsoft's vsdbg. Its license doesn't allow redistribution, so it must be installed by the user.
+    Vsdbg,
+}
+
+impl DebugAdapterKind {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "netcoredbg" => Some(Self::Netcoredbg),
+            "vsdbg" => Some(Self::Vsdbg),
+            _ => None,
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for DebugAdapterKind {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Self::parse(&value).ok_or_else(|| {
+            serde::de::Error::custom(format!("unknown debug adapter '{value}', expected \"netcoredbg\" or \"vsdbg\""))
+        })
+    }
+}
+
+/// Resolve the debug adapter command. A path from Zed's debugger settings wins, then the
+/// `debugAdapter` setting picks between the downloaded netcoredbg and a user-installed vsdbg.
+pub fn ensure_debug_adapter(
+    worktree: &Worktree,
+    settings: &RoslynSettings,
+    user_provided_path: Option<String>,
+) -> Result<Command> {
+    if let Some(path) = user_provided_path.filter(|path| !path.trim().is_empty()) {
+        debug_log!(worktree, "[csharp_roslyn] Using user-provided debug adapter: {path}");
+        return Ok(Command {
+            command: path,
+            args: vec!["--interpreter=vscode".to_string()],
+            env: Default::default(),
+        });
+    }
+
+    match settings.debug_adapter.unwrap_or(DebugAdapterKind::Netcoredbg) {
+        DebugAdapterKind::Netcoredbg => ensure_debugger(worktree, settings),
+        DebugAdapterKind::Vsdbg => {
+            // The extension is compiled to WASM, so the host OS has to be asked at runtime
+            let (platform, _) = zed::current_platform();
+            let binary_name = if platform == zed::Os::Windows { "vsdbg.exe" } else { "vsdbg" };
+            let path = settings
+                .vsdbg_path
+                .as_ref()
+                .map(|path| resolve_local_binary(path, binary_name))
+                .or_else(|| worktree.which(binary_name))
+                .ok_or_else(|| {
+                    "debugAdapter is set to vsdbg, but vsdbg was not found. vsdbg can't be downloaded \
+                    automatically; install it and set vsdbgPath, or add it to PATH."
+                        .to_string()
+                })?;
+            debug_log!(worktree, "[csharp_roslyn] Using vsdbg: {path}");
+            Ok(Command {
+                command: path,
+                args: vec!["--interpreter=vscode".to_string()],
+                env: Default::default(),
+            })
+        }
+    }
+}
+
+pub fn ensure_debugger(worktree: &Worktree, settings: &RoslynSettings) -> Result<Command> {
+    // An explicitly configured local debugger takes precedence over the GitHub download
+    let local_package = LocalPackage::from_settings(settings, "localDebuggerArchive");
+    if let Some(LocalPackage::Path(path)) = &local_package {
+        let binary = resolve_local_binary(path, get_debugger_binary_name());
+        debug_log!(worktree, "[csharp_roslyn] Using local netcoredbg: {binary}");
+        return Ok(Command {
+            command: binary,
+            args: vec!["--interpreter=vscode".to_string()],
+            env: Default::default(),
+        });
+    }
+
+    let (cache_dir, download_url, asset_name) = match local_package {
+        Some(LocalPackage::Url(url)) => (get_debugger_cache_dir("local")?, url, None),
+        _ => {
+            let asset_name = get_archive_name()?;
+            let download_url = format!(
+                "{}/releases/download/{}/{}",
+                NETCOREDBG_REPO, NETCOREDBG_VERSION, asset_name
+            );
+            (get_debugger_cache_dir(NETCOREDBG_VERSION)?, download_url, Some(asset_name))
+        }
+    };
     let debugger_binary = cache_dir.join(get_debugger_binary_name());
+    let source_file = cache_dir.join(SOURCE_MARKER_FILE);
 
-    if !debugger_binary.exists() {
-        download_and_extract_debugger(&cache_dir, worktree)?;
+    // Re-download when the binary is missing or was installed from a different URL
+    let is_installed = || {
+        debugger_binary.exists()
+            && std::fs::read_to_string(&source_file)
+                .map(|source| source.trim() == download_url)
+                .unwrap_or(false)
+    };
+    // Another Zed window may be installing the same version; wait for it before checking again
+    let _lock = if is_installed() { None } else { Some(CacheLock::acquire(&cache_dir)?) };
+    if !is_installed() {
+        let (owner, repo) = NETCOREDBG_REPO
+            .trim_start_matches("https://github.com/")
+            .split_once('/')
+            .unwrap_or_default();
+        let github_asset = asset_name.as_deref().map(|name| GithubAsset {
+            owner,
+            repo,
+            tag: NETCOREDBG_VERSION,
+            name,
+        });
+        let checksum = expected_checksum(worktree, settings, "debuggerSha256", github_asset)?;
+        let result = download_and_extract_debugger(&cache_dir, &download_url, checksum.as_deref(), worktree)
+            .and_then(|_| verify_binary(&debugger_binary));
+        if let Err(e) = result {
+            // Don't leave a partial install behind for the next attempt to trip over
+            let _ = std::fs::remove_dir_all(&cache_dir);
+            return Err(e);
+        }
+        std::fs::write(&source_file, &download_url)
+            .map_err(|e| format!("Failed to write debugger source file: {e}"))?;
     }
 
     let absolute_path = if debugger_binary.is_absolute() {
@@ -29,17 +157,160 @@ pub fn ensure_debugger(worktree: &Worktree) -> Result<Command> {
     })
 }
 
-fn get_debugger_cache_dir() -> Result<PathBuf> {
-    let cache_dir = Path::new("cache")
-        .join("netcoredbg")
-        .join(NETCOREDBG_VERSION);
+/// Debug options that can be defaulted from the `debugOptions` setting
+const DEFAULTABLE_DEBUG_OPTIONS: &[&str] = &[
+    "justMyCode",
+    "enableStepFiltering",
+    "symbolOptions",
+    "sourceFileMap",
+    "suppressJITOptimizations",
+];
+
+/// Fill debug options the configuration doesn't set from the user's `debugOptions` defaults.
+/// Stepping into package code needs optimizations suppressed, so `justMyCode` defaults to
+/// off when `suppressJITOptimizations` is on, and to on otherwise.
+pub fn apply_debug_option_defaults(config: &mut serde_json::Value, defaults: Option<&serde_json::Value>) {
+    let Some(obj) = config.as_object_mut() else {
+        return;
+    };
+
+    if let Some(defaults) = defaults.and_then(|defaults| defaults.as_object()) {
+        for key in DEFAULTABLE_DEBUG_OPTIONS {
+            if let Some(value) = defaults.get(*key) {
+                obj.entry(key.to_string()).or_insert_with(|| value.clone());
+            }
+        }
+    }
+
+    let suppress_jit = obj
+        .get("suppressJITOptimizations")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    obj.entry("justMyCode").or_insert(serde_json::Value::Bool(!suppress_jit));
+}
+
+/// Read the `debugOptions` defaults from user settings
+pub fn get_debug_option_defaults(settings: &RoslynSettings) -> Option<serde_json::Value> {
+    settings.debug_options.clone().map(serde_json::Value::Object)
+}
+
+/// Parse a dotenv file: `KEY=value` lines with optional `export ` prefixes, `#` comments, and
+/// quoted values. `$ZED_WORKTREE_ROOT` (or `${ZED_WORKTREE_ROOT}`) in values expands to `worktree_root`.
+pub fn parse_env_file(text: &str, worktree_root: &str) -> Vec<(String, String)> {
+    text.lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                return None;
+            }
+            let (key, value) = line.strip_prefix("export ").unwrap_or(line).split_once('=')?;
+            let value = value.trim();
+            let value = match value.chars().next() {
+                Some(quote @ ('"' | '\'')) if value.len() >= 2 && value.ends_with(quote) => &value[1..value.len() - 1],
+                _ => value.split(" #").next().unwrap_or(value).trim_end(),
+            };
+            Some((key.trim().to_string(), expand_worktree_root(value, worktree_root)))
+        })
+        .filter(|(key, _)| !key.is_empty())
+        .collect()
+}
+
+fn expand_worktree_root(value: &str, worktree_root: &str) -> String {
+    value
+        .replace("${ZED_WORKTREE_ROOT}", worktree_root)
+        .replace("$ZED_WORKTREE_ROOT", worktree_root)
+}
+
+/// Merge the configuration's `envFile` into its `env`. Variables set in `env` take precedence.
+/// The file must be inside the worktree, since that's all the extension can read.
+pub fn apply_env_file(config: &mut serde_json::Value, worktree: &Worktree) -> Result<()> {
+    let Some(obj) = config.as_object_mut() else {
+        return Ok(());
+    };
+    let Some(env_file) = obj.remove("envFile") else {
+        return Ok(());
+    };
+    let env_file = env_file
+        .as_str()
+        .ok_or_else(|| "envFile must be a path".to_string())?;
+
+    let root = worktree.root_path();
+    let path = expand_worktree_root(env_file, &root);
+    let relative = match path.strip_prefix(root.as_str()) {
+        Some(relative) => relative.trim_start_matches(['/', '\\']),
+        None if Path::new(&path).is_relative() => path.as_str(),
+        None => return Err(format!("envFile {env_file} must be inside the worktree")),
+    };
+    let text = worktree
+        .read_text_file(relative)
+        .map_err(|e| format!("Failed to read envFile {env_file}: {e}"))?;
+    let variables = parse_env_file(&text, &root);
+    debug_log!(worktree, "[csharp_roslyn] Loaded {} variable(s) from {env_file}", variables.len());
+
+    let env = obj.entry("env").or_insert_with(|| serde_json::json!({}));
+    if !env.is_object() {
+        *env = serde_json::json!({});
+    }
+    if let Some(env) = env.as_object_mut() {
+        for (key, value) in variables {
+            env.entry(key).or_insert(serde_json::Value::String(value));
+        }
+    }
+    Ok(())
+}
+
+/// Build the adapter command for a `pipeTransport` configuration. netcoredbg runs on the remote
+/// host or inside the container and speaks DAP over the pipe program's stdio, e.g.
+/// `ssh user@host netcoredbg --interpreter=vscode` or `docker exec -i app netcoredbg --interpreter=vscode`.
+/// Returns `None` when the configuration debugs locally.
+pub fn pipe_transport_command(config: &serde_json::Value) -> Result<Option<Command>> {
+    let Some(pipe) = config.get("pipeTransport") else {
+        return Ok(None);
+    };
+
+    let pipe_program = pipe
+        .get("pipeProgram")
+        .and_then(|v| v.as_str())
+        .filter(|program| !program.trim().is_empty())
+        .ok_or_else(|| "pipeTransport requires a pipeProgram (e.g. \"ssh\" or \"docker\")".to_string())?;
+    let mut args: Vec<String> = pipe
+        .get("pipeArgs")
+        .and_then(|v| v.as_array())
+        .map(|args| {
+            args.iter()
+                .filter_map(|arg| arg.as_str())
+                .map(|arg| arg.to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+    let debugger_path = pipe
+        .get("debuggerPath")
+        .and_then(|v| v.as_str())
+        .unwrap_or("netcoredbg");
+    args.push(debugger_path.to_string());
+    args.push("--interpreter=vscode".to_string());
+
+    Ok(Some(Command {
+        command: pipe_program.to_string(),
+        args,
+        env: Default::default(),
+    }))
+}
+
+fn get_debugger_cache_dir(version: &str) -> Result<PathBuf> {
+    let cache_dir = Path::new("cache").join("netcoredbg").join(version);
     std::fs::create_dir_all(&cache_dir)
         .map_err(|e| format!("Failed to create debugger cache directory: {e}"))?;
     Ok(cache_dir)
 }
 
 fn get_debugger_binary_name() -> &'static str {
-    if cfg!(target_os = "windows") {
+    debugger_binary_name(zed::current_platform().0)
+}
+
+/// The extension is compiled to WASM, so the host OS is passed in rather than taken from `cfg!`
+fn debugger_binary_name(os: zed::Os) -> &'static str {
+    if os == zed::Os::Windows {
         "netcoredbg.exe"
     } else {
         "netcoredbg"
@@ -61,29 +332,24 @@ fn get_platform_suffix() -> Result<String> {
     Ok(platform.to_string())
 }
 
-fn download_and_extract_debugger(cache_dir: &Path, worktree: &Worktree) -> Result<()> {
+fn get_archive_name() -> Result<String> {
     let platform = get_platform_suffix()?;
-    let is_windows = cfg!(target_os = "windows");
-    let (archive_name, file_type) = if is_windows {
-        (
-            format!("netcoredbg-{}.zip", platform),
-            zed::DownloadedFileType::Zip,
-        )
+    if zed::current_platform().0 == zed::Os::Windows {
+        Ok(format!("netcoredbg-{}.zip", platform))
     } else {
-        (
-            format!("netcoredbg-{}.tar.gz", platform),
-            zed::DownloadedFileType::GzipTar,
-        )
-    };
-    let download_url = format!(
-        "{}/releases/download/{}/{}",
-        NETCOREDBG_REPO, NETCOREDBG_VERSION, archive_name
-    );
+        Ok(format!("netcoredbg-{}.tar.gz", platform))
+    }
+}
 
+fn download_and_extract_debugger(
+    cache_dir: &Path,
+    download_url: &str,
+    expected_sha256: Option<&str>,
+    worktree: &Worktree,
+) -> Result<()> {
     debug_log!(worktree, "[csharp_roslyn] Attempting to download netcoredbg from: {download_url}");
 
-    let cache_dir_str = cache_dir.to_string_lossy().to_string();
-    zed::download_file(&download_url, &cache_dir_str, file_type)
+    download_verified(download_url, cache_dir, expected_sha256)
         .map_err(|e| format!("Failed to download netcoredbg from {}: {e}", download_url))?;
 
     let debugger_binary = cache_dir.join(get_debugger_binary_name());
@@ -111,9 +377,79 @@ mod tests {
         assert_ne!(suffix, "unknown");
     }
 
+    #[test]
+    fn test_pipe_transport_command() {
+        let config = serde_json::json!({
+            "request": "attach",
+            "processId": 1,
+            "pipeTransport": {
+                "pipeProgram": "docker",
+                "pipeArgs": ["exec", "-i", "api"],
+                "debuggerPath": "/vsdbg/netcoredbg"
+            }
+        });
+        let command = pipe_transport_command(&config).unwrap().unwrap();
+        assert_eq!(command.command, "docker");
+        assert_eq!(command.args, vec!["exec", "-i", "api", "/vsdbg/netcoredbg", "--interpreter=vscode"]);
+
+        assert!(pipe_transport_command(&serde_json::json!({ "request": "launch" })).unwrap().is_none());
+        assert!(pipe_transport_command(&serde_json::json!({ "pipeTransport": {} })).is_err());
+    }
+
+    #[test]
+    fn test_parse_env_file() {
+        let text = "# shared launch environment\n\
+            export ASPNETCORE_ENVIRONMENT=Staging\n\
+            DATA_DIR=\"$ZED_WORKTREE_ROOT/data\"\n\
+            GREETING='hello # world'\n\
+            LOG_LEVEL=Debug # inline comment\n\
+            \n\
+            not a variable\n";
+        assert_eq!(
+            parse_env_file(text, "/src/app"),
+            vec![
+                ("ASPNETCORE_ENVIRONMENT".to_string(), "Staging".to_string()),
+                ("DATA_DIR".to_string(), "/src/app/data".to_string()),
+                ("GREETING".to_string(), "hello # world".to_string()),
+                ("LOG_LEVEL".to_string(), "Debug".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_apply_debug_option_defaults() {
+        let defaults = serde_json::json!({
+            "suppressJITOptimizations": true,
+            "sourceFileMap": { "/_/src": "/home/me/src" },
+            "program": "ignored.dll"
+        });
+        let mut config = serde_json::json!({ "request": "launch", "program": "app.dll" });
+        apply_debug_option_defaults(&mut config, Some(&defaults));
+        assert_eq!(config["justMyCode"], false);
+        assert_eq!(config["sourceFileMap"]["/_/src"], "/home/me/src");
+        assert_eq!(config["program"], "app.dll");
+
+        // Explicit values in the configuration win
+        let mut config = serde_json::json!({ "justMyCode": true, "suppressJITOptimizations": false });
+        apply_debug_option_defaults(&mut config, Some(&defaults));
+        assert_eq!(config["justMyCode"], true);
+        assert_eq!(config["suppressJITOptimizations"], false);
+
+        let mut config = serde_json::json!({});
+        apply_debug_option_defaults(&mut config, None);
+        assert_eq!(config["justMyCode"], true);
+    }
+
+    #[test]
+    fn test_debug_adapter_kind() {
+        assert_eq!(DebugAdapterKind::parse("netcoredbg"), Some(DebugAdapterKind::Netcoredbg));
+        assert_eq!(DebugAdapterKind::parse(" VSDBG "), Some(DebugAdapterKind::Vsdbg));
+        assert_eq!(DebugAdapterKind::parse("lldb"), None);
+    }
+
     #[test]
     fn test_binary_name() {
-        let name = get_debugger_binary_name();
-        assert!(name == "netcoredbg" || name == "netcoredbg.exe");
+        assert_eq!(debugger_binary_name(zed::Os::Windows), "netcoredbg.exe");
+        assert_eq!(debugger_binary_name(zed::Os::Linux), "netcoredbg");
     }
 }
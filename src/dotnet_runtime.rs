@@ -0,0 +1,246 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use zed_extension_api::{
+    self as zed,
+    http_client::{HttpMethod, HttpRequest, RedirectPolicy},
+    Result,
+};
+
+use crate::dotnet_sdk::candidate_dotnet_paths;
+use crate::download::{download_verified, verify_binary, CacheLock};
+use crate::logging::debug_log;
+use crate::settings::RoslynSettings;
+
+/// Release channel the private runtime is pinned to (OmniSharp's net6.0 build rolls forward)
+const RUNTIME_CHANNEL: &str = "8.0";
+const RELEASE_METADATA_URL: &str = "https://builds.dotnet.microsoft.com/dotnet/release-metadata";
+/// Oldest Microsoft.NETCore.App major version able to host the language server
+const MIN_RUNTIME_MAJOR: u64 = 6;
+const VERSION_MARKER_FILE: &str = "version.txt";
+
+/// A downloadable runtime archive resolved from the channel's release metadata
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeAsset {
+    pub version: String,
+    pub url: String,
+    pub sha512: String,
+}
+
+/// Map Zed's platform to the .NET runtime identifier used in release metadata
+fn get_runtime_identifier(platform: zed::Os, arch: zed::Architecture) -> Result<&'static str> {
+    match (platform, arch) {
+        (zed::Os::Mac, zed::Architecture::Aarch64) => Ok("osx-arm64"),
+        (zed::Os::Mac, zed::Architecture::X8664) => Ok("osx-x64"),
+        (zed::Os::Linux, zed::Architecture::Aarch64) => Ok("linux-arm64"),
+        (zed::Os::Linux, zed::Architecture::X8664) => Ok("linux-x64"),
+        (zed::Os::Windows, zed::Architecture::Aarch64) => Ok("win-arm64"),
+        (zed::Os::Windows, zed::Architecture::X8664) => Ok("win-x64"),
+        _ => Err(format!("Unsupported platform for .NET runtime: {:?} {:?}", platform, arch)),
+    }
+}
+
+/// Pick the latest runtime archive for `rid` from a channel's `releases.json`
+pub fn parse_release_metadata(text: &str, rid: &str) -> Option<RuntimeAsset> {
+    let metadata: serde_json::Value = serde_json::from_str(text).ok()?;
+    let latest = metadata.get("latest-runtime")?.as_str()?;
+    let runtime = metadata
+        .get("releases")?
+        .as_array()?
+        .iter()
+        .filter_map(|release| release.get("runtime"))
+        .find(|runtime| runtime.get("version").and_then(|v| v.as_str()) == Some(latest))?;
+
+    let file = runtime.get("files")?.as_array()?.iter().find(|file| {
+        let name = file.get("name").and_then(|n| n.as_str()).unwrap_or_default();
+        file.get("rid").and_then(|r| r.as_str()) == Some(rid)
+            && name.starts_with("dotnet-runtime-")
+            && (name.ends_with(".tar.gz") || name.ends_with(".zip"))
+    })?;
+
+    Some(RuntimeAsset {
+        version: latest.to_string(),
+        url: file.get("url")?.as_str()?.to_string(),
+        sha512: file.get("hash")?.as_str()?.to_lowercase(),
+    })
+}
+
+/// Check `dotnet --list-runtimes` output for a Microsoft.NETCore.App runtime new enough to host OmniSharp
+pub fn has_usable_runtime(list_runtimes_output: &str) -> bool {
+    list_runtimes_output.lines().any(|line| {
+        let mut parts = line.split_whitespace();
+        parts.next() == Some("Microsoft.NETCore.App")
+            && parts
+                .next()
+                .and_then(|version| version.split('.').next())
+                .and_then(|major| major.parse::<u64>().ok())
+                .is_some_and(|major| major >= MIN_RUNTIME_MAJOR)
+    })
+}
+
+/// Whether any discoverable `dotnet` installation provides a usable runtime
+fn system_runtime_available(worktree: &zed::Worktree, env: &[(String, String)]) -> bool {
+    candidate_dotnet_paths(worktree, env).iter().any(|dotnet| {
+        zed::process::Command::new(dotnet.as_str())
+            .arg("--list-runtimes")
+            .output()
+            .ok()
+            .filter(|output| output.status == Some(0))
+            .is_some_and(|output| has_usable_runtime(&String::from_utf8_lossy(&output.stdout)))
+    })
+}
+
+/// Whether the user allows downloading a private runtime (`autoInstallDotnetRuntime`, default on)
+fn auto_install_enabled(settings: &RoslynSettings) -> bool {
+    settings.auto_install_dotnet_runtime.unwrap_or(true)
+}
+
+fn fetch_runtime_asset(rid: &str) -> Result<RuntimeAsset> {
+    let url = format!("{RELEASE_METADATA_URL}/{RUNTIME_CHANNEL}/releases.json");
+    let response = HttpRequest::builder()
+        .method(HttpMethod::Get)
+        .url(&url)
+        .redirect_policy(RedirectPolicy::FollowAll)
+        .build()?
+        .fetch()
+        .map_err(|e| format!("Failed to fetch .NET release metadata from {url}: {e}"))?;
+
+    parse_release_metadata(&String::from_utf8_lossy(&response.body), rid)
+        .ok_or_else(|| format!("No .NET {RUNTIME_CHANNEL} runtime for {rid} found in {url}"))
+}
+
+/// Find a previously installed private runtime (the marker is only written after a complete install)
+fn find_installed_runtime(cache_dir: &Path) -> Option<PathBuf> {
+    fs::read_dir(cache_dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .find(|path| path.join(VERSION_MARKER_FILE).exists())
+}
+
+/// Ensure a .NET runtime is available to host the language server.
+///
+/// Returns `Ok(None)` when a system runtime exists (or auto-install is disabled); otherwise
+/// downloads a private runtime pinned to the release channel into the extension cache and
+/// returns its absolute root directory, suitable for `DOTNET_ROOT`.
+pub fn ensure_dotnet_runtime(
+    language_server_id: &zed::LanguageServerId,
+    worktree: &zed::Worktree,
+    settings: &RoslynSettings,
+    env: &[(String, String)],
+) -> Result<Option<String>> {
+    if !auto_install_enabled(settings) || system_runtime_available(worktree, env) {
+        return Ok(None);
+    }
+    debug_log!(worktree, "[csharp_roslyn] No .NET runtime found; using a private runtime");
+
+    let cache_dir = Path::new("cache").join("dotnet-runtime");
+    fs::create_dir_all(&cache_dir)
+        .map_err(|e| format!("Failed to create .NET runtime cache directory: {e}"))?;
+
+    // Another Zed window may be installing the runtime; wait for it before checking again
+    let _lock = match find_installed_runtime(&cache_dir) {
+        Some(_) => None,
+        None => Some(CacheLock::acquire(&cache_dir)?),
+    };
+    let runtime_dir = match find_installed_runtime(&cache_dir) {
+        Some(dir) => dir,
+        None => {
+            let (platform, arch) = zed::current_platform();
+            let rid = get_runtime_identifier(platform, arch)?;
+            zed::set_language_server_installation_status(
+                language_server_id,
+                &zed::LanguageServerInstallationStatus::Downloading,
+            );
+
+            let result = fetch_runtime_asset(rid).and_then(|asset| {
+                debug_log!(worktree, "[csharp_roslyn] Downloading .NET runtime {} from {}", asset.version, asset.url);
+                let dir = cache_dir.join(&asset.version);
+                let _ = fs::remove_dir_all(&dir);
+                fs::create_dir_all(&dir)
+                    .map_err(|e| format!("Failed to create .NET runtime directory: {e}"))?;
+
+                let dotnet = dir.join(if platform == zed::Os::Windows { "dotnet.exe" } else { "dotnet" });
+                let installed = download_verified(&asset.url, &dir, Some(&asset.sha512))
+                    .and_then(|_| verify_binary(&dotnet))
+                    .and_then(|_| {
+                        if platform != zed::Os::Windows {
+                            zed::make_file_executable(&dotnet.to_string_lossy())?;
+                        }
+                        fs::write(dir.join(VERSION_MARKER_FILE), &asset.version)
+                            .map_err(|e| format!("Failed to write .NET runtime version file: {e}"))
+                    });
+                if installed.is_err() {
+                    let _ = fs::remove_dir_all(&dir);
+                }
+                installed.map(|_| dir)
+            });
+
+            match result {
+                Ok(dir) => dir,
+                Err(e) => {
+                    zed::set_language_server_installation_status(
+                        language_server_id,
+                        &zed::LanguageServerInstallationStatus::Failed(e.clone()),
+                    );
+                    return Err(e);
+                }
+            }
+        }
+    };
+
+    let absolute = std::env::current_dir()
+        .map_err(|e| format!("Failed to get current directory: {e}"))?
+        .join(runtime_dir);
+    Ok(Some(absolute.to_string_lossy().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const METADATA: &str = r#"{
+        "channel-version": "8.0",
+        "latest-runtime": "8.0.11",
+        "releases": [
+            {
+                "release-version": "8.0.11",
+                "runtime": {
+                    "version": "8.0.11",
+                    "files": [
+                        { "name": "dotnet-runtime-linux-x64.tar.gz", "rid": "linux-x64", "url": "https://example/linux-x64.tar.gz", "hash": "ABC" },
+                        { "name": "dotnet-runtime-win-x64.exe", "rid": "win-x64", "url": "https://example/win-x64.exe", "hash": "DEF" },
+                        { "name": "dotnet-runtime-win-x64.zip", "rid": "win-x64", "url": "https://example/win-x64.zip", "hash": "123" }
+                    ]
+                }
+            },
+            {
+                "release-version": "8.0.10",
+                "runtime": { "version": "8.0.10", "files": [] }
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn test_parse_release_metadata() {
+        let asset = parse_release_metadata(METADATA, "linux-x64").unwrap();
+        assert_eq!(asset.version, "8.0.11");
+        assert_eq!(asset.url, "https://example/linux-x64.tar.gz");
+        assert_eq!(asset.sha512, "abc");
+
+        // Installers are skipped in favor of archives
+        let asset = parse_release_metadata(METADATA, "win-x64").unwrap();
+        assert_eq!(asset.url, "https://example/win-x64.zip");
+
+        assert!(parse_release_metadata(METADATA, "osx-arm64").is_none());
+    }
+
+    #[test]
+    fn test_has_usable_runtime() {
+        let output = "Microsoft.AspNetCore.App 8.0.4 [/usr/share/dotnet/shared/Microsoft.AspNetCore.App]\n\
+                      Microsoft.NETCore.App 8.0.4 [/usr/share/dotnet/shared/Microsoft.NETCore.App]";
+        assert!(has_usable_runtime(output));
+        assert!(!has_usable_runtime("Microsoft.NETCore.App 3.1.32 [/usr/share/dotnet/shared/Microsoft.NETCore.App]"));
+        assert!(!has_usable_runtime(""));
+    }
+}
@@ -0,0 +1,299 @@
+use semver::Version;
+use serde::Deserialize;
+use zed_extension_api::{self as zed, Result};
+
+use crate::logging::debug_log;
+
+/// SDK requirements declared in a `global.json` file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SdkRequirement {
+    pub version: String,
+    #[serde(default = "default_roll_forward")]
+    pub roll_forward: String,
+    #[serde(default = "default_allow_prerelease")]
+    pub allow_prerelease: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct GlobalJson {
+    sdk: Option<SdkRequirement>,
+}
+
+fn default_roll_forward() -> String {
+    "latestPatch".to_string()
+}
+
+fn default_allow_prerelease() -> bool {
+    true
+}
+
+/// A `dotnet` installation together with the SDKs it provides
+#[derive(Debug, Clone)]
+pub struct DotnetInstallation {
+    /// Path to the `dotnet` executable
+    pub dotnet_path: String,
+    /// Install root reported by `dotnet --list-sdks` (suitable for `DOTNET_ROOT`). This is not
+    /// the executable's directory when `dotnet` is a symlink, e.g. `/usr/bin/dotnet`.
+    pub root: Option<String>,
+    pub sdks: Vec<String>,
+}
+
+impl DotnetInstallation {
+    /// Environment overrides that make `dotnet`, run by name, resolve to this installation:
+    /// its root goes first on `env`'s `PATH` and becomes `DOTNET_ROOT`. Empty when the root is unknown.
+    pub fn command_env(&self, env: &[(String, String)]) -> Vec<(String, String)> {
+        let Some(root) = &self.root else {
+            return Vec::new();
+        };
+        let separator = if zed::current_platform().0 == zed::Os::Windows { ";" } else { ":" };
+        let path = match env.iter().find(|(key, _)| key.eq_ignore_ascii_case("PATH")) {
+            Some((_, path)) => format!("{root}{separator}{path}"),
+            None => root.clone(),
+        };
+        vec![("PATH".to_string(), path), ("DOTNET_ROOT".to_string(), root.clone())]
+    }
+}
+
+/// Parse the `sdk` section of a `global.json` file
+pub fn parse_global_json(text: &str) -> Option<SdkRequirement> {
+    serde_json::from_str::<GlobalJson>(text).ok()?.sdk
+}
+
+/// Parse the output of `dotnet --list-sdks` (e.g. `8.0.204 [/usr/share/dotnet/sdk]`) into versions
+pub fn parse_list_sdks(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .filter(|version| Version::parse(version).is_ok())
+        .map(|version| version.to_string())
+        .collect()
+}
+
+/// Find the install root in the output of `dotnet --list-sdks`: the parent of the bracketed
+/// `sdk` directory (`8.0.204 [/usr/share/dotnet/sdk]` -> `/usr/share/dotnet`)
+pub fn parse_sdk_root(output: &str) -> Option<String> {
+    output.lines().find_map(|line| {
+        let sdk_dir = line.split_once('[')?.1.trim_end().strip_suffix(']')?;
+        let root = sdk_dir.trim_end_matches(['/', '\\']);
+        let idx = root.rfind(['/', '\\'])?;
+        Some(root[..idx].to_string())
+    })
+}
+
+/// Check whether an installed SDK version satisfies a `global.json` requirement,
+/// following the SDK's `rollForward` policy rules.
+pub fn is_compatible(installed: &str, requirement: &SdkRequirement) -> bool {
+    let (Ok(installed), Ok(required)) = (
+        Version::parse(installed),
+        Version::parse(requirement.version.trim()),
+    ) else {
+        return false;
+    };
+
+    if !installed.pre.is_empty() && !requirement.allow_prerelease {
+        return false;
+    }
+
+    // SDK patch numbers encode the feature band in the hundreds (8.0.204 -> band 2, patch 4)
+    let same_minor = installed.major == required.major && installed.minor == required.minor;
+    let same_feature_band = same_minor && installed.patch / 100 == required.patch / 100;
+
+    match requirement.roll_forward.as_str() {
+        "disable" => installed == required,
+        "patch" | "latestPatch" => same_feature_band && installed >= required,
+        "feature" | "latestFeature" => same_minor && installed >= required,
+        "minor" | "latestMinor" => installed.major == required.major && installed >= required,
+        "major" | "latestMajor" => installed >= required,
+        _ => same_feature_band && installed >= required,
+    }
+}
+
+/// Candidate `dotnet` executables, in priority order: `DOTNET_ROOT`, `~/.dotnet`,
+/// whatever is on `PATH`, then the platform's default system install locations.
+pub(crate) fn candidate_dotnet_paths(worktree: &zed::Worktree, env: &[(String, String)]) -> Vec<String> {
+    let (platform, _) = zed::current_platform();
+    let is_windows = platform == zed::Os::Windows;
+    let exe = if is_windows { "dotnet.exe" } else { "dotnet" };
+    let sep = if is_windows { '\\' } else { '/' };
+    let env_var = |key: &str| {
+        env.iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.clone())
+            .filter(|v| !v.is_empty())
+    };
+
+    let mut candidates = Vec::new();
+    if let Some(root) = env_var("DOTNET_ROOT") {
+        candidates.push(format!("{}{sep}{exe}", root.trim_end_matches(['/', '\\'])));
+    }
+    let home = if is_windows { env_var("USERPROFILE") } else { env_var("HOME") };
+    if let Some(home) = home {
+        candidates.push(format!("{home}{sep}.dotnet{sep}{exe}"));
+    }
+    if let Some(path) = worktree.which(exe) {
+        candidates.push(path);
+    }
+    let system_paths: &[&str] = match platform {
+        zed::Os::Windows => &["C:\\Program Files\\dotnet\\dotnet.exe"],
+        zed::Os::Mac => &["/usr/local/share/dotnet/dotnet"],
+        zed::Os::Linux => &["/usr/share/dotnet/dotnet", "/usr/lib/dotnet/dotnet", "/usr/local/share/dotnet/dotnet"],
+    };
+    candidates.extend(system_paths.iter().map(|p| p.to_string()));
+
+    let mut seen = std::collections::HashSet::new();
+    candidates.retain(|c| seen.insert(c.clone()));
+    candidates
+}
+
+/// Run `dotnet --list-sdks` for a candidate executable and return its output. Returns `None`
+/// if it can't be run.
+fn list_sdks(dotnet_path: &str) -> Option<String> {
+    let output = zed::process::Command::new(dotnet_path)
+        .arg("--list-sdks")
+        .output()
+        .ok()?;
+    if output.status != Some(0) {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn installation_for(dotnet_path: String, list_sdks_output: &str) -> DotnetInstallation {
+    DotnetInstallation {
+        dotnet_path,
+        root: parse_sdk_root(list_sdks_output),
+        sdks: parse_list_sdks(list_sdks_output),
+    }
+}
+
+/// Find the first `dotnet` installation that provides an SDK, for running builds. A private
+/// runtime has no SDKs, so it never qualifies.
+pub fn find_sdk_installation(worktree: &zed::Worktree, env: &[(String, String)]) -> Option<DotnetInstallation> {
+    candidate_dotnet_paths(worktree, env).into_iter().find_map(|candidate| {
+        let output = list_sdks(&candidate)?;
+        let install = installation_for(candidate, &output);
+        (!install.sdks.is_empty() && install.root.is_some()).then_some(install)
+    })
+}
+
+/// Select a `dotnet` installation whose SDKs satisfy the worktree's `global.json`.
+///
+/// Returns `Ok(None)` when there is no `global.json` pin (or no installation could be
+/// inspected), so the default `dotnet` lookup applies. Returns an error listing the
+/// installed SDKs when a pin exists but nothing installed satisfies it.
+pub fn resolve_dotnet_for_worktree(
+    worktree: &zed::Worktree,
+    env: &[(String, String)],
+) -> Result<Option<DotnetInstallation>> {
+    let Some(requirement) = worktree
+        .read_text_file("global.json")
+        .ok()
+        .and_then(|text| parse_global_json(&text))
+    else {
+        return Ok(None);
+    };
+    debug_log!(
+        worktree,
+        "[csharp_roslyn] global.json requires SDK {} (rollForward: {})",
+        requirement.version,
+        requirement.roll_forward
+    );
+
+    let mut inspected = Vec::new();
+    for candidate in candidate_dotnet_paths(worktree, env) {
+        let Some(output) = list_sdks(&candidate) else {
+            continue;
+        };
+        let install = installation_for(candidate, &output);
+        debug_log!(worktree, "[csharp_roslyn] {} provides SDKs: {:?}", install.dotnet_path, install.sdks);
+        if install.sdks.iter().any(|sdk| is_compatible(sdk, &requirement)) {
+            return Ok(Some(install));
+        }
+        inspected.push(install);
+    }
+
+    if inspected.is_empty() {
+        debug_log!(worktree, "[csharp_roslyn] No dotnet installation could be inspected; skipping global.json check");
+        return Ok(None);
+    }
+
+    let installed = inspected
+        .iter()
+        .map(|install| format!("  {}: {}", install.dotnet_path, install.sdks.join(", ")))
+        .collect::<Vec<_>>()
+        .join("\n");
+    Err(format!(
+        "global.json requires .NET SDK {} (rollForward: {}), but no compatible SDK is installed.\n\
+        Installed SDKs:\n{}\n\
+        Install a matching SDK from https://dotnet.microsoft.com/download or update global.json.",
+        requirement.version, requirement.roll_forward, installed
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn requirement(version: &str, roll_forward: &str) -> SdkRequirement {
+        SdkRequirement {
+            version: version.to_string(),
+            roll_forward: roll_forward.to_string(),
+            allow_prerelease: false,
+        }
+    }
+
+    #[test]
+    fn test_parse_global_json() {
+        let req = parse_global_json(r#"{ "sdk": { "version": "8.0.100", "rollForward": "latestFeature" } }"#).unwrap();
+        assert_eq!(req.version, "8.0.100");
+        assert_eq!(req.roll_forward, "latestFeature");
+        assert!(req.allow_prerelease);
+
+        let req = parse_global_json(r#"{ "sdk": { "version": "6.0.400" } }"#).unwrap();
+        assert_eq!(req.roll_forward, "latestPatch");
+
+        assert!(parse_global_json(r#"{ "msbuild-sdks": {} }"#).is_none());
+    }
+
+    #[test]
+    fn test_parse_list_sdks() {
+        let output = "6.0.428 [/usr/share/dotnet/sdk]\n8.0.204 [/usr/share/dotnet/sdk]\n\n";
+        assert_eq!(parse_list_sdks(output), vec!["6.0.428", "8.0.204"]);
+    }
+
+    #[test]
+    fn test_installation_for_symlinked_dotnet() {
+        let install = installation_for("/usr/bin/dotnet".to_string(), "8.0.204 [/usr/lib/dotnet/sdk]\n");
+        assert_eq!(install.root.as_deref(), Some("/usr/lib/dotnet"));
+        assert_eq!(install.sdks, vec!["8.0.204"]);
+
+        let install = installation_for(
+            "dotnet.exe".to_string(),
+            "9.0.100 [C:\\Program Files\\dotnet\\sdk]\r\n",
+        );
+        assert_eq!(install.root.as_deref(), Some("C:\\Program Files\\dotnet"));
+
+        assert_eq!(installation_for("/usr/bin/dotnet".to_string(), "").root, None);
+    }
+
+    #[test]
+    fn test_is_compatible_roll_forward() {
+        assert!(is_compatible("8.0.105", &requirement("8.0.100", "latestPatch")));
+        assert!(!is_compatible("8.0.204", &requirement("8.0.100", "latestPatch")));
+        assert!(is_compatible("8.0.204", &requirement("8.0.100", "latestFeature")));
+        assert!(!is_compatible("9.0.100", &requirement("8.0.100", "latestMinor")));
+        assert!(is_compatible("9.0.100", &requirement("8.0.100", "latestMajor")));
+        assert!(!is_compatible("7.0.410", &requirement("8.0.100", "latestMajor")));
+        assert!(is_compatible("8.0.100", &requirement("8.0.100", "disable")));
+        assert!(!is_compatible("8.0.101", &requirement("8.0.100", "disable")));
+    }
+
+    #[test]
+    fn test_is_compatible_prerelease() {
+        let mut req = requirement("9.0.100", "latestMajor");
+        assert!(!is_compatible("10.0.100-preview.1.25080.5", &req));
+        req.allow_prerelease = true;
+        assert!(is_compatible("10.0.100-preview.1.25080.5", &req));
+    }
+}
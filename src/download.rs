@@ -0,0 +1,478 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use flate2::read::GzDecoder;
+use sha2::{Digest, Sha256, Sha512};
+use zed_extension_api::{
+    self as zed,
+    http_client::{HttpMethod, HttpRequest, RedirectPolicy},
+    Result,
+};
+
+use crate::logging::debug_log;
+use crate::settings::RoslynSettings;
+
+const DOWNLOAD_ATTEMPTS: u32 = 3;
+const INITIAL_RETRY_BACKOFF: Duration = Duration::from_secs(2);
+/// How long to wait for another window's install before giving up
+const LOCK_WAIT: Duration = Duration::from_secs(300);
+const LOCK_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// A lock this old was left behind by a crashed or killed install
+const STALE_LOCK_AGE: Duration = Duration::from_secs(900);
+
+/// A user-provided replacement for a package normally downloaded from the internet.
+/// Used for offline/air-gapped installs where GitHub and nuget.org are unreachable.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LocalPackage {
+    /// An archive hosted on an internal mirror or feed; downloaded and extracted into the cache.
+    Url(String),
+    /// An already-extracted package directory (or the binary inside it) on the local machine.
+    Path(String),
+}
+
+impl LocalPackage {
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if value.is_empty() {
+            return None;
+        }
+
+        if value.starts_with("http://") || value.starts_with("https://") {
+            Some(LocalPackage::Url(value.to_string()))
+        } else {
+            Some(LocalPackage::Path(value.to_string()))
+        }
+    }
+
+    /// Read a local package setting from the language server's initialization options
+    pub fn from_settings(settings: &RoslynSettings, key: &str) -> Option<Self> {
+        Self::parse(settings.download_setting(key)?)
+    }
+}
+
+/// Resolve the binary inside a local package path. The path may point at the binary itself
+/// or at the directory it was extracted to.
+pub fn resolve_local_binary(path: &str, binary_name: &str) -> String {
+    let trimmed = path.trim_end_matches(['/', '\\']);
+    let file_name = trimmed.rsplit(['/', '\\']).next().unwrap_or(trimmed);
+    if file_name == binary_name {
+        return trimmed.to_string();
+    }
+
+    let separator = if trimmed.contains('\\') && !trimmed.contains('/') {
+        '\\'
+    } else {
+        '/'
+    };
+    format!("{trimmed}{separator}{binary_name}")
+}
+
+/// Pick the archive type for a download URL based on its extension
+pub fn file_type_for_url(url: &str) -> zed::DownloadedFileType {
+    let path = url.split(['?', '#']).next().unwrap_or(url).to_lowercase();
+    if path.ends_with(".zip") || path.ends_with(".nupkg") || path.ends_with(".vsix") {
+        zed::DownloadedFileType::Zip
+    } else if path.ends_with(".gz") || path.ends_with(".tgz") {
+        zed::DownloadedFileType::GzipTar
+    } else {
+        zed::DownloadedFileType::Uncompressed
+    }
+}
+
+/// Read a string-valued download setting (e.g. a pinned checksum) from initialization options
+pub fn get_download_setting(settings: &RoslynSettings, key: &str) -> Option<String> {
+    let value = settings.download_setting(key)?.trim().to_string();
+    (!value.is_empty()).then_some(value)
+}
+
+/// Fetch the SHA-256 digest GitHub records for a release asset.
+/// Returns `Ok(None)` when the release exists but GitHub has no digest for the asset.
+pub fn fetch_github_asset_digest(owner: &str, repo: &str, tag: &str, asset_name: &str) -> Result<Option<String>> {
+    let request = HttpRequest::builder()
+        .method(HttpMethod::Get)
+        .url(format!("https://api.github.com/repos/{owner}/{repo}/releases/tags/{tag}"))
+        .header("Accept", "application/vnd.github+json")
+        .header("User-Agent", "zed-csharp-roslyn")
+        .redirect_policy(RedirectPolicy::FollowAll)
+        .build()?;
+    let response = request
+        .fetch()
+        .map_err(|e| format!("Failed to fetch release metadata for {owner}/{repo}@{tag}: {e}"))?;
+    let release: serde_json::Value = serde_json::from_slice(&response.body)
+        .map_err(|e| format!("Failed to parse release metadata for {owner}/{repo}@{tag}: {e}"))?;
+
+    Ok(release
+        .get("assets")
+        .and_then(|assets| assets.as_array())
+        .and_then(|assets| {
+            assets
+                .iter()
+                .find(|asset| asset.get("name").and_then(|n| n.as_str()) == Some(asset_name))
+        })
+        .and_then(|asset| asset.get("digest"))
+        .and_then(|digest| digest.as_str())
+        .and_then(|digest| digest.strip_prefix("sha256:"))
+        .map(|digest| digest.to_lowercase()))
+}
+
+/// A release asset published on GitHub, used to look up its recorded checksum
+pub struct GithubAsset<'a> {
+    pub owner: &'a str,
+    pub repo: &'a str,
+    pub tag: &'a str,
+    pub name: &'a str,
+}
+
+/// Resolve the SHA-256 a download must match: a checksum pinned in settings wins, then the
+/// digest GitHub records for the release asset. When neither is available the download is
+/// unverified, which is only an error if `requireVerifiedDownloads` is enabled.
+pub fn expected_checksum(
+    worktree: &zed::Worktree,
+    settings: &RoslynSettings,
+    setting_key: &str,
+    github_asset: Option<GithubAsset>,
+) -> Result<Option<String>> {
+    if let Some(pinned) = get_download_setting(settings, setting_key) {
+        return Ok(Some(pinned.to_lowercase()));
+    }
+
+    let digest = match github_asset {
+        Some(asset) => match fetch_github_asset_digest(asset.owner, asset.repo, asset.tag, asset.name) {
+            Ok(digest) => digest,
+            Err(e) => {
+                debug_log!(worktree, "[csharp_roslyn] Could not look up checksum: {e}");
+                None
+            }
+        },
+        None => None,
+    };
+
+    if digest.is_none() && settings.require_verified_downloads {
+        return Err(format!(
+            "No checksum available to verify this download. Set '{setting_key}' or disable 'requireVerifiedDownloads'."
+        ));
+    }
+
+    Ok(digest)
+}
+
+/// Compute the lowercase hex SHA-256 digest of a byte slice
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Compute the lowercase hex SHA-512 digest of a byte slice
+pub fn sha512_hex(bytes: &[u8]) -> String {
+    Sha512::digest(bytes)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Download an archive into `target_dir`, verify it against `expected_digest` (when given),
+/// and only then extract it. The digest may be SHA-256 or SHA-512 hex; the algorithm is
+/// picked by its length. The archive is removed afterwards, and on a checksum mismatch
+/// nothing is extracted.
+pub fn download_verified(url: &str, target_dir: &Path, expected_digest: Option<&str>) -> Result<()> {
+    let file_type = file_type_for_url(url);
+    let archive_name = url
+        .split(['?', '#'])
+        .next()
+        .and_then(|path| path.rsplit('/').next())
+        .filter(|name| !name.is_empty())
+        .unwrap_or("download");
+    let archive_path = target_dir.join(archive_name);
+
+    download_file_with_retry(url, &archive_path)?;
+
+    let bytes = fs::read(&archive_path).map_err(|e| format!("Failed to read downloaded file: {e}"))?;
+
+    if let Some(expected) = expected_digest {
+        let expected = expected.trim();
+        let actual = if expected.len() == 128 {
+            sha512_hex(&bytes)
+        } else {
+            sha256_hex(&bytes)
+        };
+        if !actual.eq_ignore_ascii_case(expected) {
+            let _ = fs::remove_file(&archive_path);
+            return Err(format!(
+                "Checksum mismatch for {url}: expected {expected}, got {actual}. Refusing to install."
+            ));
+        }
+    }
+
+    match file_type {
+        zed::DownloadedFileType::GzipTar => {
+            extract_tar_gz(&bytes, target_dir).map_err(|e| format!("Failed to extract {archive_name}: {e}"))?;
+        }
+        zed::DownloadedFileType::Zip => {
+            extract_zip(&bytes, target_dir).map_err(|e| format!("Failed to extract {archive_name}: {e}"))?;
+        }
+        // Plain files are used as downloaded
+        _ => return Ok(()),
+    }
+
+    let _ = fs::remove_file(&archive_path);
+    Ok(())
+}
+
+/// Download a single file, retrying with exponential backoff on failure.
+/// Partial files from failed attempts are removed before the next try.
+fn download_file_with_retry(url: &str, path: &Path) -> Result<()> {
+    let mut backoff = INITIAL_RETRY_BACKOFF;
+    let mut last_error = String::new();
+
+    for attempt in 1..=DOWNLOAD_ATTEMPTS {
+        match zed::download_file(url, &path.to_string_lossy(), zed::DownloadedFileType::Uncompressed) {
+            Ok(()) => return Ok(()),
+            Err(e) => {
+                let _ = fs::remove_file(path);
+                last_error = e;
+                if attempt < DOWNLOAD_ATTEMPTS {
+                    std::thread::sleep(backoff);
+                    backoff *= 2;
+                }
+            }
+        }
+    }
+
+    Err(format!(
+        "Failed to download {url} after {DOWNLOAD_ATTEMPTS} attempts: {last_error}"
+    ))
+}
+
+/// Exclusive claim on a cache directory while a package is downloaded into it. Zed windows in
+/// separate processes share the extension's cache, so two of them starting at once would
+/// otherwise extract over each other. The lock is a `<dir>.lock` file next to the directory,
+/// removed on drop; one older than `STALE_LOCK_AGE` is left from a crash and taken over.
+pub struct CacheLock {
+    path: PathBuf,
+}
+
+impl CacheLock {
+    /// Take the lock on `dir`, waiting for another install to finish
+    pub fn acquire(dir: &Path) -> Result<Self> {
+        Self::acquire_within(dir, LOCK_WAIT)
+    }
+
+    fn acquire_within(dir: &Path, wait: Duration) -> Result<Self> {
+        let mut path = dir.as_os_str().to_owned();
+        path.push(".lock");
+        let path = PathBuf::from(path);
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create cache directory: {e}"))?;
+        }
+
+        let started = SystemTime::now();
+        loop {
+            match fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(_) => return Ok(Self { path }),
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    let age = fs::metadata(&path)
+                        .and_then(|metadata| metadata.modified())
+                        .ok()
+                        .and_then(|modified| modified.elapsed().ok());
+                    if age.is_some_and(|age| age > STALE_LOCK_AGE) {
+                        let _ = fs::remove_file(&path);
+                        continue;
+                    }
+                    if started.elapsed().map_or(true, |waited| waited >= wait) {
+                        return Err(format!(
+                            "{} is being installed by another Zed window; try again once it finishes",
+                            dir.display()
+                        ));
+                    }
+                    std::thread::sleep(LOCK_POLL_INTERVAL);
+                }
+                Err(e) => return Err(format!("Failed to lock {}: {e}", dir.display())),
+            }
+        }
+    }
+}
+
+impl Drop for CacheLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Sanity-check an extracted binary: it must exist and be non-empty
+pub fn verify_binary(path: &Path) -> Result<()> {
+    match fs::metadata(path) {
+        Ok(metadata) if metadata.is_file() && metadata.len() > 0 => Ok(()),
+        Ok(_) => Err(format!("Binary at {} is empty or not a file", path.display())),
+        Err(_) => Err(format!("Binary not found at {}", path.display())),
+    }
+}
+
+/// Extract a zip archive entry by entry. `ZipArchive::extract` canonicalizes the target
+/// directory, which isn't supported under WASI.
+fn extract_zip(bytes: &[u8], target_dir: &Path) -> std::io::Result<()> {
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(bytes))?;
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        // Skip entries that would escape the target directory
+        let Some(relative_path) = entry.enclosed_name() else {
+            continue;
+        };
+        let out_path = target_dir.join(relative_path);
+
+        if entry.is_dir() {
+            fs::create_dir_all(&out_path)?;
+            continue;
+        }
+        if let Some(parent) = out_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut out_file = fs::File::create(&out_path)?;
+        std::io::copy(&mut entry, &mut out_file)?;
+    }
+    Ok(())
+}
+
+/// Extract a `.tar.gz` archive entry by entry. `Archive::unpack` sets file permissions and
+/// canonicalizes paths, neither of which is supported under WASI; callers mark the binaries
+/// they need as executable through Zed instead. Links and special files are skipped.
+fn extract_tar_gz(bytes: &[u8], target_dir: &Path) -> std::io::Result<()> {
+    let mut archive = tar::Archive::new(GzDecoder::new(bytes));
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        // Skip entries that would escape the target directory
+        let path = entry.path()?.into_owned();
+        let enclosed = path
+            .components()
+            .all(|component| matches!(component, std::path::Component::Normal(_) | std::path::Component::CurDir));
+        if !enclosed {
+            continue;
+        }
+        let out_path = target_dir.join(&path);
+
+        match entry.header().entry_type() {
+            tar::EntryType::Directory => fs::create_dir_all(&out_path)?,
+            tar::EntryType::Regular | tar::EntryType::Continuous => {
+                if let Some(parent) = out_path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                let mut out_file = fs::File::create(&out_path)?;
+                std::io::copy(&mut entry, &mut out_file)?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local_package_parse() {
+        assert_eq!(
+            LocalPackage::parse("https://mirror.local/omnisharp.tar.gz"),
+            Some(LocalPackage::Url("https://mirror.local/omnisharp.tar.gz".to_string()))
+        );
+        assert_eq!(
+            LocalPackage::parse("/opt/omnisharp"),
+            Some(LocalPackage::Path("/opt/omnisharp".to_string()))
+        );
+        assert_eq!(LocalPackage::parse("  "), None);
+    }
+
+    #[test]
+    fn test_resolve_local_binary() {
+        assert_eq!(resolve_local_binary("/opt/omnisharp/", "OmniSharp"), "/opt/omnisharp/OmniSharp");
+        assert_eq!(resolve_local_binary("/opt/omnisharp/OmniSharp", "OmniSharp"), "/opt/omnisharp/OmniSharp");
+        assert_eq!(
+            resolve_local_binary("C:\\tools\\netcoredbg", "netcoredbg.exe"),
+            "C:\\tools\\netcoredbg\\netcoredbg.exe"
+        );
+    }
+
+    #[test]
+    fn test_sha256_hex() {
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_extract_zip() {
+        use std::io::Write;
+
+        let mut buffer = std::io::Cursor::new(Vec::new());
+        let mut writer = zip::ZipWriter::new(&mut buffer);
+        writer
+            .start_file("bin/tool.txt", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(b"hello").unwrap();
+        writer.finish().unwrap();
+
+        let target = std::env::temp_dir().join(format!("csharp_roslyn_zip_{}", std::process::id()));
+        extract_zip(buffer.get_ref(), &target).unwrap();
+        assert_eq!(fs::read_to_string(target.join("bin/tool.txt")).unwrap(), "hello");
+        let _ = fs::remove_dir_all(&target);
+    }
+
+    #[test]
+    fn test_extract_tar_gz() {
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default()));
+        let mut header = tar::Header::new_gnu();
+        header.set_size(5);
+        header.set_mode(0o755);
+        header.set_cksum();
+        builder.append_data(&mut header, "./bin/tool", &b"hello"[..]).unwrap();
+        let mut header = tar::Header::new_gnu();
+        header.set_size(4);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, "README.md", &b"docs"[..]).unwrap();
+        let bytes = builder.into_inner().unwrap().finish().unwrap();
+
+        let target = std::env::temp_dir().join(format!("csharp_roslyn_tar_{}", std::process::id()));
+        extract_tar_gz(&bytes, &target).unwrap();
+        assert_eq!(fs::read_to_string(target.join("bin/tool")).unwrap(), "hello");
+        assert_eq!(fs::read_to_string(target.join("README.md")).unwrap(), "docs");
+        let _ = fs::remove_dir_all(&target);
+    }
+
+    #[test]
+    fn test_verify_binary() {
+        let dir = std::env::temp_dir().join(format!("csharp_roslyn_verify_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let empty = dir.join("empty");
+        fs::write(&empty, b"").unwrap();
+        assert!(verify_binary(&empty).is_err());
+
+        let binary = dir.join("binary");
+        fs::write(&binary, b"\x7fELF").unwrap();
+        assert!(verify_binary(&binary).is_ok());
+
+        assert!(verify_binary(&dir.join("missing")).is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_cache_lock() {
+        let dir = std::env::temp_dir().join(format!("csharp_roslyn_lock_{}", std::process::id())).join("1.0.0");
+        let lock = CacheLock::acquire_within(&dir, Duration::ZERO).unwrap();
+        assert!(CacheLock::acquire_within(&dir, Duration::ZERO).is_err());
+        drop(lock);
+        assert!(CacheLock::acquire_within(&dir, Duration::ZERO).is_ok());
+        let _ = fs::remove_dir_all(dir.parent().unwrap());
+    }
+
+    #[test]
+    fn test_file_type_for_url() {
+        assert!(matches!(file_type_for_url("https://x/a.zip"), zed::DownloadedFileType::Zip));
+        assert!(matches!(file_type_for_url("https://x/a.tar.gz?sig=1"), zed::DownloadedFileType::GzipTar));
+        assert!(matches!(file_type_for_url("https://x/a"), zed::DownloadedFileType::Uncompressed));
+    }
+}
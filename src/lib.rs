@@ -1,10 +1,21 @@
 use zed_extension_api as zed;
 
+mod container;
 mod csharp;
 mod debugger;
+mod dotnet_runtime;
+mod dotnet_sdk;
+mod download;
 mod logging;
+mod msbuild;
+mod nuget;
 mod omnisharp_download;
+mod processes;
 mod project_info;
+mod scaffolding;
+mod settings;
+mod support;
+mod unity_debugger;
 
 pub use csharp::CsharpRoslynExtension;
 
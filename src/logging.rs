@@ -1,31 +1,183 @@
-use zed_extension_api::{settings::LspSettings, Worktree};
-
-/// Check if debug logging is enabled via extension setting or debug build
-pub fn is_debug_enabled(worktree: &Worktree) -> bool {
-    // Always enable in debug builds
-    if cfg!(debug_assertions) {
-        return true;
-    }
-    
-    // Check extension setting via LSP settings
-    if let Ok(settings) = LspSettings::for_worktree("csharp_roslyn", worktree) {
-        if let Some(init_options) = settings.initialization_options {
-            if let Some(enable_debug) = init_options.get("enable_debug_logging") {
-                return enable_debug.as_bool().unwrap_or(false);
-            }
+use std::cell::RefCell;
+use std::time::{Duration, Instant};
+use zed_extension_api::Worktree;
+
+use crate::settings::RoslynSettings;
+
+/// Replace the user's home directory with `~`, so shared logs don't reveal user names
+pub fn redact_home(message: &str, home: Option<&str>) -> String {
+    let Some(home) = home.map(|home| home.trim_end_matches(['/', '\\'])).filter(|home| home.len() > 1) else {
+        return message.to_string();
+    };
+    let message = message.replace(home, "~");
+    // Windows paths may show up with either separator
+    let forward = home.replace('\\', "/");
+    if forward != home {
+        message.replace(&forward, "~")
+    } else {
+        message
+    }
+}
+
+/// How `debug_log!` writes messages about a worktree, taken from its settings
+pub struct LogConfig {
+    worktree_id: u64,
+    enabled: bool,
+    unsafe_full_logs: bool,
+    home: Option<String>,
+}
+
+impl LogConfig {
+    pub fn new(worktree: &Worktree, settings: &RoslynSettings) -> Self {
+        // The home directory is only needed when messages are redacted
+        let home = if settings.unsafe_full_logs {
+            None
+        } else {
+            worktree
+                .shell_env()
+                .into_iter()
+                .find(|(key, _)| key == "HOME" || key == "USERPROFILE")
+                .map(|(_, value)| value)
+        };
+        Self {
+            worktree_id: worktree.id(),
+            // Always enable in debug builds
+            enabled: cfg!(debug_assertions) || settings.enable_debug_logging,
+            unsafe_full_logs: settings.unsafe_full_logs,
+            home,
+        }
+    }
+
+    /// Redact a log message unless `unsafeFullLogs` is set
+    pub fn redact(&self, message: &str) -> String {
+        if self.unsafe_full_logs {
+            return message.to_string();
+        }
+        redact_home(message, self.home.as_deref())
+    }
+
+    fn write(&self, message: impl FnOnce() -> String) {
+        if self.enabled {
+            eprintln!("{}", self.redact(&message()));
+        }
+    }
+}
+
+thread_local! {
+    static ACTIVE: RefCell<Option<LogConfig>> = const { RefCell::new(None) };
+}
+
+/// Keeps a worktree's [`LogConfig`] active until dropped
+pub struct LogScope {
+    previous: Option<LogConfig>,
+}
+
+impl Drop for LogScope {
+    fn drop(&mut self) {
+        ACTIVE.set(self.previous.take());
+    }
+}
+
+/// Log with `settings` until the returned scope is dropped. Entry points take a scope after
+/// loading the settings, so `debug_log!` doesn't read them again for every message.
+pub fn scope(worktree: &Worktree, settings: &RoslynSettings) -> LogScope {
+    LogScope {
+        previous: ACTIVE.replace(Some(LogConfig::new(worktree, settings))),
+    }
+}
+
+/// Write a debug log message, formatting it only when debug logging is on. Outside a [`scope`]
+/// the settings are read for the message.
+pub fn log(worktree: &Worktree, message: impl FnOnce() -> String) {
+    let scoped = ACTIVE.with_borrow(|active| match active {
+        Some(config) if config.worktree_id == worktree.id() => {
+            config.write(message);
+            None
+        }
+        _ => Some(message),
+    });
+    if let Some(message) = scoped {
+        let settings = RoslynSettings::for_worktree(worktree);
+        if cfg!(debug_assertions) || settings.enable_debug_logging {
+            LogConfig::new(worktree, &settings).write(message);
+        }
+    }
+}
+
+/// Time spent in each step of the extension's part of language server startup
+pub struct StartupTimeline {
+    started: Instant,
+    last: Instant,
+    steps: Vec<(&'static str, Duration)>,
+}
+
+impl StartupTimeline {
+    pub fn start() -> Self {
+        let now = Instant::now();
+        Self {
+            started: now,
+            last: now,
+            steps: Vec::new(),
         }
     }
-    
-    false
+
+    /// Record the time since the previous step under `name`
+    pub fn step(&mut self, name: &'static str) {
+        let now = Instant::now();
+        self.steps.push((name, now - self.last));
+        self.last = now;
+    }
+
+    pub fn summary(&self) -> String {
+        format_timeline(&self.steps, self.last - self.started)
+    }
+}
+
+/// Format steps as `name 1.20s, name 0.05s (total 1.25s)`
+fn format_timeline(steps: &[(&str, Duration)], total: Duration) -> String {
+    let steps: Vec<String> = steps
+        .iter()
+        .map(|(name, duration)| format!("{name} {:.2}s", duration.as_secs_f64()))
+        .collect();
+    format!("{} (total {:.2}s)", steps.join(", "), total.as_secs_f64())
 }
 
 /// Debug logging macro that checks both debug_assertions and extension setting
 macro_rules! debug_log {
     ($worktree:expr, $($arg:tt)*) => {
-        if crate::logging::is_debug_enabled($worktree) {
-            eprintln!($($arg)*);
-        }
+        crate::logging::log($worktree, || format!($($arg)*))
     };
 }
 
-pub(crate) use debug_log;
\ No newline at end of file
+pub(crate) use debug_log;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_timeline() {
+        let steps = [
+            ("design-time builds", Duration::from_millis(3400)),
+            ("OmniSharp", Duration::from_millis(50)),
+        ];
+        assert_eq!(
+            format_timeline(&steps, Duration::from_millis(3450)),
+            "design-time builds 3.40s, OmniSharp 0.05s (total 3.45s)"
+        );
+    }
+
+    #[test]
+    fn test_redact_home() {
+        assert_eq!(
+            redact_home("Using /home/alice/.dotnet/dotnet for /home/alice/src/App.sln", Some("/home/alice/")),
+            "Using ~/.dotnet/dotnet for ~/src/App.sln"
+        );
+        assert_eq!(
+            redact_home(r"C:\Users\alice\src and C:/Users/alice/.nuget", Some(r"C:\Users\alice")),
+            r"~\src and ~/.nuget"
+        );
+        assert_eq!(redact_home("/home/alice/src", None), "/home/alice/src");
+        assert_eq!(redact_home("/src", Some("/")), "/src");
+    }
+}
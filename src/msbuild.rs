@@ -0,0 +1,431 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use zed_extension_api::{self as zed, Result, SlashCommandOutput, SlashCommandOutputSection, Worktree};
+
+use crate::download::sha256_hex;
+use crate::logging::{self, debug_log};
+use crate::processes::command_on_path;
+use crate::settings::RoslynSettings;
+use crate::scaffolding::{configured_solution, resolve_project};
+
+/// Properties requested from `dotnet msbuild -getProperty`. The list is spelled out in the
+/// extension.toml capability, so keep the two in sync.
+const EVALUATED_PROPERTIES: &[&str] = &["TargetPath", "TargetFramework", "AssemblyName", "OutputPath"];
+
+/// Properties of a project as evaluated by MSBuild, including imports and conditions
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EvaluatedProject {
+    pub properties: HashMap<String, String>,
+}
+
+impl EvaluatedProject {
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.properties
+            .get(name)
+            .map(|value| value.as_str())
+            .filter(|value| !value.is_empty())
+    }
+}
+
+/// Parse `-getProperty` output. With several properties MSBuild prints
+/// `{ "Properties": { "Name": "value", ... } }`.
+pub fn parse_get_property_output(output: &str) -> Option<EvaluatedProject> {
+    let json: serde_json::Value = serde_json::from_str(output.trim()).ok()?;
+    let properties = json
+        .get("Properties")?
+        .as_object()?
+        .iter()
+        .filter_map(|(name, value)| Some((name.clone(), value.as_str()?.to_string())))
+        .collect();
+    Some(EvaluatedProject { properties })
+}
+
+/// Whether MSBuild evaluation is enabled (`msbuildEvaluation`, default on)
+fn evaluation_enabled(settings: &RoslynSettings) -> bool {
+    settings.msbuild_evaluation.unwrap_or(true)
+}
+
+/// `-bl:<extension dir>/logs/<name>.binlog` when `msbuildBinaryLog` is enabled, so builds leave a
+/// binary log to inspect or attach to bug reports without writing into the user's repository
+fn binary_log_arg(worktree: &Worktree, settings: &RoslynSettings, name: &str) -> Option<String> {
+    if !settings.msbuild_binary_log {
+        return None;
+    }
+    if let Err(e) = fs::create_dir_all("logs") {
+        debug_log!(worktree, "[csharp_roslyn] Failed to create the binary log directory: {e}");
+        return None;
+    }
+    // MSBuild runs on the host, so it needs the absolute path of the extension directory
+    let log = std::env::current_dir().ok()?.join("logs").join(format!("{name}.binlog"));
+    Some(format!("-bl:{}", log.to_string_lossy()))
+}
+
+/// Evaluate a project with `dotnet msbuild -getProperty`, for accuracy beyond text scraping.
+///
+/// Results are cached in the extension directory keyed by the project text (including imported
+/// props), configuration, and framework, so edits to the project invalidate them. Returns `None`
+/// when evaluation is disabled, `dotnet` isn't available, or MSBuild fails; callers then fall back
+/// to the lightweight parser.
+pub fn evaluate_project(
+    worktree: &Worktree,
+    settings: &RoslynSettings,
+    project_path: &str,
+    project_text: &str,
+    configuration: &str,
+    target_framework: &str,
+) -> Option<EvaluatedProject> {
+    if !evaluation_enabled(settings) {
+        return None;
+    }
+
+    let cache_key = sha256_hex(format!("{project_path}\n{configuration}\n{target_framework}\n{project_text}").as_bytes());
+    let cache_file = Path::new("cache").join("msbuild").join(format!("{cache_key}.json"));
+    if let Some(cached) = fs::read_to_string(&cache_file)
+        .ok()
+        .and_then(|text| parse_get_property_output(&text))
+    {
+        return Some(cached);
+    }
+
+    worktree.which("dotnet")?;
+    let output = command_on_path(worktree, "dotnet")
+        .args([
+            "msbuild".to_string(),
+            project_path.to_string(),
+            format!("-getProperty:{}", EVALUATED_PROPERTIES.join(",")),
+            format!("-p:Configuration={configuration}"),
+            format!("-p:TargetFramework={target_framework}"),
+        ])
+        .output()
+        .ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if output.status != Some(0) {
+        debug_log!(worktree, "[csharp_roslyn] MSBuild evaluation of {project_path} failed: {}", stdout.trim());
+        return None;
+    }
+
+    let evaluated = parse_get_property_output(&stdout)?;
+    debug_log!(worktree, "[csharp_roslyn] MSBuild evaluated {project_path}: {:?}", evaluated.properties);
+    if let Some(parent) = cache_file.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let _ = fs::write(&cache_file, stdout.as_bytes());
+    Some(evaluated)
+}
+
+/// Whether a project generates XAML code-behind partials (`InitializeComponent`) at build time
+pub fn is_xaml_project(project_text: &str) -> bool {
+    let text = project_text.to_lowercase().replace(char::is_whitespace, "");
+    text.contains("<usewpf>true</usewpf>") || text.contains("<usewinui>true</usewinui>")
+}
+
+/// Whether a project generates C# from `.proto` files with Grpc.Tools
+pub fn is_grpc_project(project_text: &str) -> bool {
+    let text = project_text.to_lowercase();
+    text.contains("include=\"grpc.tools\"") || text.contains("<protobuf ")
+}
+
+/// The files of a project's `<Protobuf Include="..."/>` items, relative to the project directory.
+/// Globs are left as they are.
+pub fn protobuf_files(project_text: &str) -> Vec<String> {
+    project_text
+        .split("<Protobuf ")
+        .skip(1)
+        .filter_map(|item| {
+            let (_, rest) = item.split_once("Include=\"")?;
+            let (include, _) = rest.split_once('"')?;
+            Some(include.to_string())
+        })
+        .flat_map(|include| {
+            include
+                .split(';')
+                .map(|file| file.trim().replace('\\', "/"))
+                .filter(|file| !file.is_empty())
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Run a design-time build so files generated at build time exist in `obj/` before OmniSharp
+/// loads the project: WPF/WinUI code-behind partials (otherwise `InitializeComponent` and named
+/// elements are reported missing) and Grpc.Tools classes. Runs once per project text and
+/// `generator_inputs` (anything else that feeds code generation, such as `.proto` files), tracked
+/// by a marker in the extension directory. Failed builds get a marker too, so they aren't retried
+/// until the inputs change. Packages are restored only when the project text changed since the
+/// last successful restore, so editing a `.proto` file doesn't pay for a restore.
+///
+/// `env` holds the `PATH` and `DOTNET_ROOT` that select the SDK to build with.
+pub fn run_design_time_build(
+    worktree: &Worktree,
+    settings: &RoslynSettings,
+    env: &[(String, String)],
+    project_path: &str,
+    project_text: &str,
+    generator_inputs: &str,
+) {
+    let cache_dir = Path::new("cache").join("design-time");
+    let marker = cache_dir.join(sha256_hex(format!("{project_path}\n{project_text}{generator_inputs}").as_bytes()));
+    if marker.exists() {
+        return;
+    }
+    let restore_marker = cache_dir.join(format!(
+        "restored-{}",
+        sha256_hex(format!("{project_path}\n{project_text}").as_bytes())
+    ));
+    let restore = !restore_marker.exists();
+
+    debug_log!(worktree, "[csharp_roslyn] Running design-time build of {project_path} (restore: {restore})");
+    let project_name = Path::new(project_path)
+        .file_stem()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut args = vec!["msbuild".to_string(), project_path.to_string()];
+    if restore {
+        args.push("-restore".to_string());
+    }
+    args.extend(["-t:Compile", "-p:DesignTimeBuild=true", "-p:SkipCompilerExecution=true"].map(|arg| arg.to_string()));
+    args.extend(binary_log_arg(worktree, settings, &format!("design-time-{project_name}")));
+    let output = match zed::process::Command::new("dotnet").args(args).envs(env.iter().cloned()).output() {
+        Ok(output) => output,
+        Err(e) => {
+            // dotnet couldn't be started at all; try again once it's installed
+            debug_log!(worktree, "[csharp_roslyn] Design-time build of {project_path} failed: {e}");
+            return;
+        }
+    };
+    let _ = fs::create_dir_all(&cache_dir);
+    let outcome = if output.status == Some(0) {
+        if restore {
+            let _ = fs::write(&restore_marker, project_path);
+        }
+        "succeeded"
+    } else if !restore {
+        // obj/ may have been cleaned since the last restore; restore on the next start
+        debug_log!(
+            worktree,
+            "[csharp_roslyn] Design-time build of {project_path} without restore failed: {}",
+            String::from_utf8_lossy(&output.stdout).trim()
+        );
+        let _ = fs::remove_file(&restore_marker);
+        return;
+    } else {
+        debug_log!(
+            worktree,
+            "[csharp_roslyn] Design-time build of {project_path} failed; not retrying until the project changes: {}",
+            String::from_utf8_lossy(&output.stdout).trim()
+        );
+        "failed"
+    };
+    let _ = fs::write(&marker, format!("{outcome}: {project_path}"));
+}
+
+/// An error or warning from MSBuild output
+#[derive(Debug, Clone, PartialEq)]
+pub struct BuildDiagnostic {
+    pub file: String,
+    /// 1-based line and column, when MSBuild reports a location
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+    pub severity: String,
+    pub code: String,
+    pub message: String,
+}
+
+impl BuildDiagnostic {
+    /// `path:line:col: error CS1002: message`, the form editors and terminals link
+    pub fn to_line(&self) -> String {
+        let location = match (self.line, self.column) {
+            (Some(line), Some(column)) => format!("{}:{line}:{column}", self.file),
+            (Some(line), None) => format!("{}:{line}", self.file),
+            _ => self.file.clone(),
+        };
+        format!("{location}: {} {}: {}", self.severity, self.code, self.message)
+    }
+}
+
+/// Parse one line of MSBuild output, e.g.
+/// `/src/App/Program.cs(12,5): error CS1002: ; expected [/src/App/App.csproj]`
+pub fn parse_build_line(line: &str) -> Option<BuildDiagnostic> {
+    let line = line.trim();
+    // The earliest marker is the severity; a warning's message may itself contain ": error "
+    let (idx, marker) = [": error ", ": warning "]
+        .iter()
+        .filter_map(|marker| Some((line.find(marker)?, *marker)))
+        .min_by_key(|(idx, _)| *idx)?;
+    let (prefix, severity, rest) = (&line[..idx], marker.trim_matches([':', ' ']), &line[idx + marker.len()..]);
+    let (code, message) = rest.split_once(": ")?;
+    let message = match message.rfind(" [") {
+        Some(idx) if message.ends_with(']') => &message[..idx],
+        _ => message,
+    };
+
+    let (file, line, column) = match prefix.strip_suffix(')').and_then(|p| p.rsplit_once('(')) {
+        Some((file, location)) => {
+            let mut numbers = location.split(',').map(|n| n.trim().parse::<u32>().ok());
+            (file, numbers.next().flatten(), numbers.next().flatten())
+        }
+        None => (prefix, None, None),
+    };
+
+    Some(BuildDiagnostic {
+        file: file.trim().to_string(),
+        line,
+        column,
+        severity: severity.to_string(),
+        code: code.trim().to_string(),
+        message: message.trim().to_string(),
+    })
+}
+
+/// Parse MSBuild output into diagnostics, dropping the repeats of its summary
+pub fn parse_build_output(output: &str) -> Vec<BuildDiagnostic> {
+    let mut diagnostics: Vec<BuildDiagnostic> = Vec::new();
+    for diagnostic in output.lines().filter_map(parse_build_line) {
+        if !diagnostics.contains(&diagnostic) {
+            diagnostics.push(diagnostic);
+        }
+    }
+    diagnostics
+}
+
+/// Run `/dotnet-build [Project.csproj]`: build the project, or the configured solution, and list
+/// the compiler's errors and warnings
+pub fn run_dotnet_build(args: &[String], worktree: Option<&Worktree>) -> Result<SlashCommandOutput> {
+    let worktree = worktree.ok_or_else(|| "/dotnet-build needs an open worktree".to_string())?;
+    let settings = RoslynSettings::for_worktree(worktree);
+    let _log = logging::scope(worktree, &settings);
+    let target = match args {
+        [project] => resolve_project(worktree, project)?,
+        [] => configured_solution(worktree, &settings)
+            .ok_or_else(|| "No solution configured; pass a project: /dotnet-build <Project.csproj>".to_string())?,
+        _ => return Err("Usage: /dotnet-build [Project.csproj]".to_string()),
+    };
+    worktree
+        .which("dotnet")
+        .ok_or_else(|| "dotnet was not found on PATH".to_string())?;
+
+    let mut build_args = vec![
+        "build".to_string(),
+        target.clone(),
+        "-nologo".to_string(),
+        "-clp:NoSummary".to_string(),
+    ];
+    let binary_log = binary_log_arg(worktree, &settings, "msbuild");
+    build_args.extend(binary_log.clone());
+
+    debug_log!(worktree, "[csharp_roslyn] dotnet {}", build_args.join(" "));
+    let output = command_on_path(worktree, "dotnet")
+        .args(build_args)
+        .output()
+        .map_err(|e| format!("Failed to run dotnet build: {e}"))?;
+    let diagnostics = parse_build_output(&String::from_utf8_lossy(&output.stdout));
+
+    let mut text = String::new();
+    let mut sections = Vec::new();
+    for severity in ["error", "warning"] {
+        let lines: Vec<String> = diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == severity)
+            .map(|diagnostic| diagnostic.to_line())
+            .collect();
+        if lines.is_empty() {
+            continue;
+        }
+        let start = text.len();
+        text.push_str(&lines.join("\n"));
+        text.push('\n');
+        sections.push(SlashCommandOutputSection {
+            range: (start..text.len()).into(),
+            label: format!("{} {severity}(s)", lines.len()),
+        });
+    }
+
+    let status = if output.status == Some(0) { "Build succeeded" } else { "Build failed" };
+    if sections.is_empty() {
+        text = format!("{status}\n");
+        sections.push(SlashCommandOutputSection {
+            range: (0..text.len()).into(),
+            label: status.to_string(),
+        });
+    }
+    if let Some(log) = binary_log.as_deref().and_then(|arg| arg.strip_prefix("-bl:")) {
+        let start = text.len();
+        text.push_str(&format!("Binary log: {log}\n"));
+        sections.push(SlashCommandOutputSection {
+            range: (start..text.len()).into(),
+            label: "Binary log".to_string(),
+        });
+    }
+    Ok(SlashCommandOutput { text, sections })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_get_property_output() {
+        let output = r#"{
+  "Properties": {
+    "TargetPath": "/src/App/bin/Debug/net8.0/App.dll",
+    "TargetFramework": "net8.0",
+    "AssemblyName": "App",
+    "OutputPath": ""
+  }
+}"#;
+        let evaluated = parse_get_property_output(output).unwrap();
+        assert_eq!(evaluated.get("TargetPath"), Some("/src/App/bin/Debug/net8.0/App.dll"));
+        assert_eq!(evaluated.get("OutputPath"), None);
+        assert!(parse_get_property_output("MSBUILD : error MSB1009: Project file does not exist.").is_none());
+    }
+
+    #[test]
+    fn test_parse_build_output() {
+        let output = "  App -> /src/App/bin/Debug/net8.0/App.dll\n\
+/src/App/Program.cs(12,5): error CS1002: ; expected [/src/App/App.csproj]\n\
+/src/App/Service.cs(3,10,3,14): warning CS0168: The variable 'e' is declared but never used [/src/App/App.csproj]\n\
+/src/App/Program.cs(12,5): error CS1002: ; expected [/src/App/App.csproj]\n\
+MSBUILD : error MSB1009: Project file does not exist.\n";
+        let diagnostics = parse_build_output(output);
+        assert_eq!(diagnostics.len(), 3);
+        assert_eq!(diagnostics[0].to_line(), "/src/App/Program.cs:12:5: error CS1002: ; expected");
+        assert_eq!(diagnostics[1].severity, "warning");
+        assert_eq!((diagnostics[1].line, diagnostics[1].column), (Some(3), Some(10)));
+        assert_eq!(diagnostics[2].file, "MSBUILD");
+        assert_eq!(diagnostics[2].line, None);
+    }
+
+    #[test]
+    fn test_parse_build_line_picks_earliest_marker() {
+        let diagnostic = parse_build_line(
+            "/src/App/Program.cs(7,1): warning CS8604: Possible null reference in 'Log(\"x: error y\")' [/src/App/App.csproj]",
+        )
+        .unwrap();
+        assert_eq!(diagnostic.severity, "warning");
+        assert_eq!(diagnostic.code, "CS8604");
+        assert_eq!(diagnostic.file, "/src/App/Program.cs");
+        assert_eq!(diagnostic.message, "Possible null reference in 'Log(\"x: error y\")'");
+    }
+
+    #[test]
+    fn test_is_xaml_project() {
+        assert!(is_xaml_project("<PropertyGroup>\n  <UseWPF>true</UseWPF>\n</PropertyGroup>"));
+        assert!(is_xaml_project("<UseWinUI> true </UseWinUI>"));
+        assert!(!is_xaml_project("<UseWPF>false</UseWPF>"));
+        assert!(!is_xaml_project("<OutputType>Exe</OutputType>"));
+    }
+
+    #[test]
+    fn test_protobuf_files() {
+        let text = r#"<ItemGroup>
+    <Protobuf Include="Protos\greet.proto" GrpcServices="Server" />
+    <Protobuf Include="Protos/a.proto;Protos/b.proto" GrpcServices="Client" />
+    <PackageReference Include="Grpc.Tools" Version="2.62.0" PrivateAssets="All" />
+  </ItemGroup>"#;
+        assert!(is_grpc_project(text));
+        assert_eq!(protobuf_files(text), vec!["Protos/greet.proto", "Protos/a.proto", "Protos/b.proto"]);
+        assert!(!is_grpc_project("<PackageReference Include=\"Grpc.Net.Client\" />"));
+    }
+}
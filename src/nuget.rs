@@ -0,0 +1,162 @@
+use zed_extension_api::{
+    http_client::{HttpMethod, HttpRequest, RedirectPolicy},
+    Result, SlashCommandArgumentCompletion, SlashCommandOutput, Worktree,
+};
+
+use crate::logging::{self, debug_log};
+use crate::scaffolding::{resolve_project, run_dotnet, single_section};
+use crate::settings::RoslynSettings;
+
+const SEARCH_URL: &str = "https://azuresearch-usnc.nuget.org/query";
+const SEARCH_RESULTS: usize = 10;
+const USAGE: &str = "Usage: /nuget search <query> | /nuget add <Project.csproj> <Package> [version] | /nuget remove <Project.csproj> <Package>";
+
+/// A package returned by the nuget.org search API
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackageSearchResult {
+    pub id: String,
+    pub version: String,
+    pub description: String,
+}
+
+/// Parse a nuget.org search response (`{ "data": [{ "id", "version", "description" }, ...] }`)
+pub fn parse_search_response(text: &str) -> Vec<PackageSearchResult> {
+    let Ok(json) = serde_json::from_str::<serde_json::Value>(text) else {
+        return Vec::new();
+    };
+    json.get("data")
+        .and_then(|data| data.as_array())
+        .map(|packages| {
+            packages
+                .iter()
+                .filter_map(|package| {
+                    Some(PackageSearchResult {
+                        id: package.get("id")?.as_str()?.to_string(),
+                        version: package.get("version")?.as_str()?.to_string(),
+                        description: package
+                            .get("description")
+                            .and_then(|d| d.as_str())
+                            .unwrap_or_default()
+                            .to_string(),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Search nuget.org for packages matching `query`
+pub fn search_packages(query: &str) -> Result<Vec<PackageSearchResult>> {
+    let url = format!(
+        "{SEARCH_URL}?q={}&take={SEARCH_RESULTS}&semVerLevel=2.0.0",
+        url::form_urlencoded::byte_serialize(query.as_bytes()).collect::<String>()
+    );
+    let response = HttpRequest::builder()
+        .method(HttpMethod::Get)
+        .url(&url)
+        .redirect_policy(RedirectPolicy::FollowAll)
+        .build()?
+        .fetch()
+        .map_err(|e| format!("Failed to search nuget.org: {e}"))?;
+    Ok(parse_search_response(&String::from_utf8_lossy(&response.body)))
+}
+
+/// Complete `/nuget` arguments: the subcommand, then package ids from a nuget.org search
+pub fn complete_nuget_argument(args: &[String]) -> Result<Vec<SlashCommandArgumentCompletion>> {
+    match args {
+        [] | [_] => {
+            let query = args.first().map(|arg| arg.as_str()).unwrap_or_default();
+            Ok(["search", "add", "remove"]
+                .iter()
+                .filter(|subcommand| subcommand.starts_with(query))
+                .map(|subcommand| SlashCommandArgumentCompletion {
+                    label: subcommand.to_string(),
+                    new_text: format!("{subcommand} "),
+                    run_command: false,
+                })
+                .collect())
+        }
+        [subcommand, _project, query] if subcommand == "add" && query.len() >= 2 => Ok(search_packages(query)?
+            .into_iter()
+            .map(|package| SlashCommandArgumentCompletion {
+                label: format!("{} {}", package.id, package.version),
+                new_text: package.id,
+                run_command: true,
+            })
+            .collect()),
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// Run `/nuget`. `dotnet add package` restores the project, and OmniSharp reloads it when the
+/// project file changes, so new APIs become available without restarting the server.
+pub fn run_nuget_command(args: &[String], worktree: Option<&Worktree>) -> Result<SlashCommandOutput> {
+    let Some((subcommand, rest)) = args.split_first() else {
+        return Err(USAGE.to_string());
+    };
+
+    if subcommand == "search" {
+        let query = rest.join(" ");
+        let text = search_packages(&query)?
+            .iter()
+            .map(|package| format!("{} {}\n    {}\n", package.id, package.version, package.description.trim()))
+            .collect::<String>();
+        return Ok(single_section(text, format!("NuGet search: {query}")));
+    }
+
+    let worktree = worktree.ok_or_else(|| "/nuget needs an open worktree".to_string())?;
+    let _log = logging::scope(worktree, &RoslynSettings::for_worktree(worktree));
+    worktree
+        .which("dotnet")
+        .ok_or_else(|| "dotnet was not found on PATH".to_string())?;
+
+    let mut dotnet_args = match (subcommand.as_str(), rest) {
+        ("add", [project, package, ..]) | ("remove", [project, package]) => vec![
+            subcommand.clone(),
+            resolve_project(worktree, project)?,
+            "package".to_string(),
+            package.clone(),
+        ],
+        _ => return Err(USAGE.to_string()),
+    };
+    match rest {
+        [_, _, version] if subcommand == "add" => {
+            dotnet_args.extend(["--version".to_string(), version.clone()]);
+        }
+        [_, _] => {}
+        _ => return Err(USAGE.to_string()),
+    }
+
+    debug_log!(worktree, "[csharp_roslyn] dotnet {}", dotnet_args.join(" "));
+    let text = run_dotnet(worktree, &dotnet_args)?;
+    Ok(single_section(text, format!("dotnet {subcommand} package {}", rest[1])))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_search_response() {
+        let response = r#"{
+            "totalHits": 2,
+            "data": [
+                { "id": "Newtonsoft.Json", "version": "13.0.3", "description": "Json.NET" },
+                { "id": "Newtonsoft.Json.Bson", "version": "1.0.2" }
+            ]
+        }"#;
+        let packages = parse_search_response(response);
+        assert_eq!(packages.len(), 2);
+        assert_eq!(packages[0].id, "Newtonsoft.Json");
+        assert_eq!(packages[0].version, "13.0.3");
+        assert_eq!(packages[1].description, "");
+        assert!(parse_search_response("not json").is_empty());
+    }
+
+    #[test]
+    fn test_complete_subcommand() {
+        let completions = complete_nuget_argument(&["re".to_string()]).unwrap();
+        assert_eq!(completions.len(), 1);
+        assert_eq!(completions[0].new_text, "remove ");
+    }
+}
@@ -1,12 +1,20 @@
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 use zed_extension_api::{self as zed, Result};
 
+use crate::download::{
+    download_verified, expected_checksum, resolve_local_binary, verify_binary, CacheLock, GithubAsset,
+    LocalPackage,
+};
 use crate::logging::debug_log;
+use crate::settings::RoslynSettings;
 
-const OMNISHARP_VERSION: &str = "1.39.15-beta.69";
+pub(crate) const OMNISHARP_VERSION: &str = "1.39.15-beta.69";
 const GITHUB_REPO_OWNER: &str = "OmniSharp";
 const GITHUB_REPO_NAME: &str = "omnisharp-roslyn";
+const VERSION_MARKER_FILE: &str = "version.txt";
+const DEFAULT_CACHE_MAX_AGE_DAYS: u64 = 30;
 
 /// Get the cache directory for OmniSharp-Roslyn
 fn get_omnisharp_cache_dir() -> Result<PathBuf> {
@@ -16,29 +24,41 @@ fn get_omnisharp_cache_dir() -> Result<PathBuf> {
     Ok(cache_dir)
 }
 
-/// Get the asset name for the current platform
-fn get_platform_asset_name(platform: zed::Os, arch: zed::Architecture) -> Result<String> {
-    let asset_name = match (platform, arch) {
-        (zed::Os::Mac, zed::Architecture::Aarch64) => "omnisharp-osx-arm64-net6.0.tar.gz",
-        (zed::Os::Mac, zed::Architecture::X8664) => "omnisharp-osx-x64-net6.0.tar.gz",
-        (zed::Os::Linux, zed::Architecture::Aarch64) => "omnisharp-linux-arm64-net6.0.tar.gz",
-        (zed::Os::Linux, zed::Architecture::X8664) => "omnisharp-linux-x64-net6.0.tar.gz",
-        (zed::Os::Windows, zed::Architecture::X8664) => "omnisharp-win-x64-net6.0.zip",
-        (zed::Os::Windows, zed::Architecture::Aarch64) => "omnisharp-win-arm64-net6.0.zip",
+/// Get the asset name for the current platform. Legacy .NET Framework builds run on Mono outside Windows.
+fn get_platform_asset_name(platform: zed::Os, arch: zed::Architecture, use_modern_net: bool) -> Result<String> {
+    let asset_name = match (platform, arch, use_modern_net) {
+        (zed::Os::Mac, zed::Architecture::Aarch64, true) => "omnisharp-osx-arm64-net6.0.tar.gz",
+        (zed::Os::Mac, zed::Architecture::X8664, true) => "omnisharp-osx-x64-net6.0.tar.gz",
+        (zed::Os::Linux, zed::Architecture::Aarch64, true) => "omnisharp-linux-arm64-net6.0.tar.gz",
+        (zed::Os::Linux, zed::Architecture::X8664, true) => "omnisharp-linux-x64-net6.0.tar.gz",
+        (zed::Os::Windows, zed::Architecture::X8664, true) => "omnisharp-win-x64-net6.0.zip",
+        (zed::Os::Windows, zed::Architecture::Aarch64, true) => "omnisharp-win-arm64-net6.0.zip",
+        (zed::Os::Windows, zed::Architecture::X8664, false) => "omnisharp-win-x64.zip",
+        (zed::Os::Windows, zed::Architecture::Aarch64, false) => "omnisharp-win-arm64.zip",
+        (zed::Os::Mac | zed::Os::Linux, _, false) => "omnisharp-mono.tar.gz",
         _ => return Err(format!("Unsupported platform: {:?} {:?}", platform, arch)),
     };
     Ok(asset_name.to_string())
 }
 
-/// Get the binary name for the platform
-fn get_binary_name(platform: zed::Os) -> &'static str {
-    if platform == zed::Os::Windows {
+/// Get the binary name for the platform. The Mono build ships `OmniSharp.exe` on every platform.
+fn get_binary_name(platform: zed::Os, use_modern_net: bool) -> &'static str {
+    if platform == zed::Os::Windows || !use_modern_net {
         "OmniSharp.exe"
     } else {
         "OmniSharp"
     }
 }
 
+/// Directory name of a cached version; Framework builds live next to the .NET 6 build of the same version
+fn get_version_dir_name(version: &str, use_modern_net: bool) -> String {
+    if use_modern_net {
+        version.to_string()
+    } else {
+        format!("{version}-framework")
+    }
+}
+
 /// Get the version from a version file or string
 fn parse_version(version_str: &str) -> Option<semver::Version> {
     // Remove 'v' prefix if present
@@ -46,72 +66,138 @@ fn parse_version(version_str: &str) -> Option<semver::Version> {
     semver::Version::parse(version_str).ok()
 }
 
-/// Download OmniSharp-Roslyn from GitHub releases
-fn download_omnisharp(
-    version: &str,
-    asset_name: &str,
-    target_dir: &Path,
-    platform: zed::Os,
-) -> Result<()> {
-    let download_url = format!(
+/// Build the GitHub release download URL for an OmniSharp-Roslyn asset
+fn get_download_url(version: &str, asset_name: &str) -> String {
+    format!(
         "https://github.com/{}/{}/releases/download/v{}/{}",
         GITHUB_REPO_OWNER, GITHUB_REPO_NAME, version, asset_name
-    );
-
-    let file_type = if platform == zed::Os::Windows {
-        zed::DownloadedFileType::Zip
-    } else {
-        zed::DownloadedFileType::GzipTar
-    };
+    )
+}
 
-    zed::download_file(&download_url, &target_dir.to_string_lossy(), file_type)
+/// Download, verify, and extract an OmniSharp-Roslyn archive
+fn download_omnisharp(download_url: &str, target_dir: &Path, expected_sha256: Option<&str>) -> Result<()> {
+    download_verified(download_url, target_dir, expected_sha256)
         .map_err(|e| format!("Failed to download and extract OmniSharp: {}", e))?;
 
     Ok(())
 }
 
-/// Ensure OmniSharp-Roslyn is available, downloading if necessary
+/// Read a pinned OmniSharp version from user settings, falling back to the bundled default
+fn get_server_version(worktree: &zed::Worktree, settings: &RoslynSettings) -> String {
+    let pinned = settings
+        .server_version
+        .as_ref()
+        .map(|v| v.trim().trim_start_matches('v').to_string());
+
+    match pinned {
+        Some(version) if parse_version(&version).is_some() => version,
+        Some(version) => {
+            debug_log!(worktree, "[csharp_roslyn] Ignoring invalid serverVersion '{version}', using {OMNISHARP_VERSION}");
+            OMNISHARP_VERSION.to_string()
+        }
+        None => OMNISHARP_VERSION.to_string(),
+    }
+}
+
+/// Read the number of days an unused cached version is kept from user settings
+fn get_cache_max_age_days(settings: &RoslynSettings) -> u64 {
+    settings
+        .server_cache_max_age_days
+        .unwrap_or(DEFAULT_CACHE_MAX_AGE_DAYS)
+}
+
+/// Remove cached versions (other than `keep`) whose marker file hasn't been touched in `max_age_days`.
+/// Versions without a marker file are judged by the directory's own modification time.
+fn remove_stale_versions(cache_dir: &Path, keep: &str, max_age_days: u64, worktree: &zed::Worktree) {
+    let Ok(entries) = fs::read_dir(cache_dir) else {
+        return;
+    };
+    let max_age = Duration::from_secs(max_age_days * 24 * 60 * 60);
+    let now = SystemTime::now();
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_dir() || entry.file_name().to_string_lossy() == keep {
+            continue;
+        }
+
+        let marker = path.join(VERSION_MARKER_FILE);
+        let last_used = fs::metadata(&marker)
+            .or_else(|_| fs::metadata(&path))
+            .and_then(|metadata| metadata.modified());
+        let is_stale = match last_used {
+            Ok(time) => now.duration_since(time).map(|age| age > max_age).unwrap_or(false),
+            Err(_) => false,
+        };
+
+        if is_stale {
+            debug_log!(worktree, "[csharp_roslyn] Removing stale OmniSharp version: {path:?}");
+            let _ = fs::remove_dir_all(&path);
+        }
+    }
+}
+
+/// Ensure OmniSharp-Roslyn is available, downloading if necessary.
+///
+/// With `use_modern_net` off, the .NET Framework build is used instead (run through Mono
+/// outside Windows), so legacy Framework solutions load.
 pub fn ensure_omnisharp(
     language_server_id: &zed::LanguageServerId,
     platform: zed::Os,
     arch: zed::Architecture,
+    use_modern_net: bool,
     worktree: &zed::Worktree,
+    settings: &RoslynSettings,
 ) -> Result<String> {
-    debug_log!(worktree, "[csharp_roslyn] ensure_omnisharp called");
-    let binary_name = get_binary_name(platform);
+    debug_log!(worktree, "[csharp_roslyn] ensure_omnisharp called (modern .NET: {use_modern_net})");
+    let binary_name = get_binary_name(platform, use_modern_net);
     debug_log!(worktree, "[csharp_roslyn] Binary name: {binary_name}");
 
+    // An explicitly configured local package takes precedence over PATH and downloads
+    let local_package = LocalPackage::from_settings(settings, "localServerPackage");
+    if let Some(LocalPackage::Path(path)) = &local_package {
+        let binary_path = resolve_local_binary(path, binary_name);
+        debug_log!(worktree, "[csharp_roslyn] Using local OmniSharp package: {binary_path}");
+        return Ok(binary_path);
+    }
+
     // First, check if OmniSharp is in PATH
     if let Some(path) = worktree.which(binary_name) {
         debug_log!(worktree, "[csharp_roslyn] Found OmniSharp in PATH: {path}");
         return Ok(path);
     }
 
-    // Check the cache directory
+    // Check the cache directory. Each version lives side-by-side in its own directory
+    // so pinned and default versions don't evict each other.
     debug_log!(worktree, "[csharp_roslyn] OmniSharp not in PATH, checking cache");
+    let (version, download_url, asset_name) = match local_package {
+        Some(LocalPackage::Url(url)) => ("local".to_string(), url, None),
+        _ => {
+            let version = get_server_version(worktree, settings);
+            let asset_name = get_platform_asset_name(platform, arch, use_modern_net)?;
+            debug_log!(worktree, "[csharp_roslyn] Asset name: {asset_name}");
+            let download_url = get_download_url(&version, &asset_name);
+            (version, download_url, Some(asset_name))
+        }
+    };
+    debug_log!(worktree, "[csharp_roslyn] OmniSharp version: {version} ({download_url})");
     let cache_dir = get_omnisharp_cache_dir()?;
     debug_log!(worktree, "[csharp_roslyn] Cache dir: {cache_dir:?}");
-    let version_dir = cache_dir.join(OMNISHARP_VERSION);
-    let version_file = cache_dir.join("version.txt");
+    let version_dir_name = get_version_dir_name(&version, use_modern_net);
+    let version_dir = cache_dir.join(&version_dir_name);
+    let version_file = version_dir.join(VERSION_MARKER_FILE);
     let binary_path = version_dir.join(binary_name);
     debug_log!(worktree, "[csharp_roslyn] Binary path: {binary_path:?}");
 
-    // Check if we already have this version cached
-    let needs_download = if version_dir.exists() && version_file.exists() {
-        match fs::read_to_string(&version_file) {
-            Ok(cached_version) => {
-                let cached = parse_version(&cached_version);
-                let current = parse_version(OMNISHARP_VERSION);
-                match (cached, current) {
-                    (Some(c), Some(cur)) => c < cur,
-                    _ => cached_version.trim() != OMNISHARP_VERSION,
-                }
-            }
-            Err(_) => true,
-        }
-    } else {
-        true
+    // The marker file records the URL the version was installed from and is only written
+    // after a complete download, so its absence means the download was interrupted.
+    let is_installed = || match fs::read_to_string(&version_file) {
+        Ok(cached_source) => cached_source.trim() == download_url && binary_path.exists(),
+        Err(_) => false,
     };
+    // Another Zed window may be downloading the same version; wait for it before checking again
+    let _lock = if is_installed() { None } else { Some(CacheLock::acquire(&version_dir)?) };
+    let needs_download = !is_installed();
 
     debug_log!(worktree, "[csharp_roslyn] Needs download: {needs_download}");
 
@@ -123,9 +209,9 @@ pub fn ensure_omnisharp(
             &zed::LanguageServerInstallationStatus::Downloading,
         );
 
-        // Clean up old version if it exists
+        // Clean up a partial install of this version if it exists
         if version_dir.exists() {
-            debug_log!(worktree, "[csharp_roslyn] Cleaning up old version");
+            debug_log!(worktree, "[csharp_roslyn] Cleaning up partial install");
             let _ = fs::remove_dir_all(&version_dir);
         }
 
@@ -133,12 +219,22 @@ pub fn ensure_omnisharp(
         fs::create_dir_all(&version_dir)
             .map_err(|e| format!("Failed to create version directory: {}", e))?;
 
-        let asset_name = get_platform_asset_name(platform, arch)?;
-        debug_log!(worktree, "[csharp_roslyn] Asset name: {asset_name}");
+        let tag = format!("v{version}");
+        let github_asset = asset_name.as_deref().map(|name| GithubAsset {
+            owner: GITHUB_REPO_OWNER,
+            repo: GITHUB_REPO_NAME,
+            tag: &tag,
+            name,
+        });
 
         debug_log!(worktree, "[csharp_roslyn] Downloading OmniSharp");
-        if let Err(e) = download_omnisharp(OMNISHARP_VERSION, &asset_name, &version_dir, platform) {
+        let result = expected_checksum(worktree, settings, "serverSha256", github_asset).and_then(|checksum| {
+            debug_log!(worktree, "[csharp_roslyn] Expected checksum: {checksum:?}");
+            download_omnisharp(&download_url, &version_dir, checksum.as_deref())
+        });
+        if let Err(e) = result {
             debug_log!(worktree, "[csharp_roslyn] Download failed: {e}");
+            let _ = fs::remove_dir_all(&version_dir);
             zed::set_language_server_installation_status(
                 language_server_id,
                 &zed::LanguageServerInstallationStatus::Failed(e.clone()),
@@ -147,26 +243,29 @@ pub fn ensure_omnisharp(
         }
         debug_log!(worktree, "[csharp_roslyn] Download completed");
 
-        // Make the binary executable on Unix platforms
-        if platform != zed::Os::Windows {
+        // Make the binary executable on Unix platforms (the Mono build is launched through `mono`)
+        if platform != zed::Os::Windows && use_modern_net {
             let _ = zed::make_file_executable(&binary_path.to_string_lossy());
         }
+    }
 
-        // Write the version file
-        if let Err(e) = fs::write(&version_file, OMNISHARP_VERSION)
-            .map_err(|e| format!("Failed to write version file: {}", e))
-        {
-            zed::set_language_server_installation_status(
-                language_server_id,
-                &zed::LanguageServerInstallationStatus::Failed(e.clone()),
-            );
-            return Err(e);
-        }
+    // Write (or touch) the version marker so the cache GC sees this version as recently used
+    if let Err(e) = fs::write(&version_file, &download_url)
+        .map_err(|e| format!("Failed to write version file: {}", e))
+    {
+        zed::set_language_server_installation_status(
+            language_server_id,
+            &zed::LanguageServerInstallationStatus::Failed(e.clone()),
+        );
+        return Err(e);
     }
 
-    // Verify binary exists
-    if !binary_path.exists() {
-        let error_msg = format!("OmniSharp binary not found at {}", binary_path.display());
+    remove_stale_versions(&cache_dir, &version_dir_name, get_cache_max_age_days(settings), worktree);
+
+    // Verify the binary was extracted intact; drop the install so the next start re-downloads
+    if let Err(error_msg) = verify_binary(&binary_path) {
+        debug_log!(worktree, "[csharp_roslyn] {error_msg}");
+        let _ = fs::remove_dir_all(&version_dir);
         zed::set_language_server_installation_status(
             language_server_id,
             &zed::LanguageServerInstallationStatus::Failed(error_msg.clone()),
@@ -0,0 +1,267 @@
+use std::path::Path;
+use std::time::Duration;
+use zed_extension_api::{self as zed, Result, Worktree};
+
+use crate::logging::debug_log;
+
+/// How long to wait for a test host started with `VSTEST_HOST_DEBUG=1` to appear
+const TEST_HOST_WAIT_ATTEMPTS: u32 = 60;
+const TEST_HOST_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A running process as reported by the platform's process listing
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    /// Full command line on Unix, image name on Windows
+    pub command: String,
+}
+
+/// Parse `ps -eo pid=,args=` output
+pub fn parse_ps_output(output: &str) -> Vec<ProcessInfo> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (pid, command) = line.trim().split_once(char::is_whitespace)?;
+            Some(ProcessInfo {
+                pid: pid.parse().ok()?,
+                command: command.trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Parse `tasklist /FO CSV /NH` output (`"image","pid","session","#","mem"`)
+pub fn parse_tasklist_output(output: &str) -> Vec<ProcessInfo> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split("\",\"").map(|field| field.trim_matches('"'));
+            let command = fields.next()?.to_string();
+            let pid = fields.next()?.parse().ok()?;
+            Some(ProcessInfo { pid, command })
+        })
+        .collect()
+}
+
+/// A command that runs `program` by name, since the `process:exec` capabilities match the
+/// command name rather than a resolved path. The worktree's `PATH` is passed along so the
+/// program is found where `Worktree::which` finds it.
+pub fn command_on_path(worktree: &Worktree, program: &str) -> zed::process::Command {
+    let command = zed::process::Command::new(program);
+    match worktree
+        .shell_env()
+        .into_iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case("PATH"))
+    {
+        Some((key, path)) => command.env(key, path),
+        None => command,
+    }
+}
+
+/// List running processes
+pub fn list_processes() -> Result<Vec<ProcessInfo>> {
+    let (platform, _) = zed::current_platform();
+    let output = if platform == zed::Os::Windows {
+        zed::process::Command::new("tasklist").args(["/FO", "CSV", "/NH"]).output()
+    } else {
+        zed::process::Command::new("ps").args(["-eo", "pid=,args="]).output()
+    }
+    .map_err(|e| format!("Failed to list processes: {e}"))?;
+
+    if output.status != Some(0) {
+        return Err(format!(
+            "Failed to list processes: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(if platform == zed::Os::Windows {
+        parse_tasklist_output(&stdout)
+    } else {
+        parse_ps_output(&stdout)
+    })
+}
+
+/// Whether a process is a VSTest test host (`testhost`, `testhost.exe`, or `dotnet exec .../testhost.dll`)
+fn is_test_host(process: &ProcessInfo) -> bool {
+    process
+        .command
+        .split_whitespace()
+        .any(|part| {
+            let name = part.rsplit(['/', '\\']).next().unwrap_or(part).to_lowercase();
+            name.starts_with("testhost") && !name.ends_with(".runtimeconfig.json")
+        })
+}
+
+/// Whether a process runs the given program, either as an apphost (`MyApp`, `MyApp.exe`)
+/// or through the muxer (`dotnet MyApp.dll`, `dotnet exec .../MyApp.dll`)
+fn matches_process_name(process: &ProcessInfo, name: &str) -> bool {
+    let name = name.trim_end_matches(".exe").trim_end_matches(".dll").to_lowercase();
+    let mut parts = process.command.split_whitespace().map(|part| {
+        part.rsplit(['/', '\\']).next().unwrap_or(part).to_lowercase()
+    });
+
+    let Some(executable) = parts.next() else {
+        return false;
+    };
+    if executable.trim_end_matches(".exe") == name {
+        return true;
+    }
+    let dll = format!("{name}.dll");
+    executable.trim_end_matches(".exe") == "dotnet" && parts.any(|part| part == dll)
+}
+
+/// Find the PID of the running process for `name` (an assembly or executable name).
+/// Fails when nothing matches, or when the match is ambiguous.
+pub fn find_process_id(name: &str, worktree: &Worktree) -> Result<u32> {
+    let matches: Vec<ProcessInfo> = list_processes()?
+        .into_iter()
+        .filter(|process| matches_process_name(process, name))
+        .collect();
+    debug_log!(worktree, "[csharp_roslyn] Processes matching '{name}': {matches:?}");
+
+    match matches.as_slice() {
+        [] => Err(format!("No running process found for '{name}'")),
+        [process] => Ok(process.pid),
+        _ => Err(format!(
+            "Several processes match '{name}' ({}); set processId instead",
+            matches
+                .iter()
+                .map(|process| process.pid.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+/// `dir` and its parent directories below the worktree root, closest first. The root itself is
+/// left out, since every test host in the worktree mentions it.
+fn test_dir_candidates(dir: &str, root_path: &str) -> Vec<String> {
+    let root = Path::new(root_path);
+    let dir = Path::new(dir);
+    if !dir.starts_with(root) {
+        return vec![dir.to_string_lossy().replace('\\', "/")];
+    }
+    dir.ancestors()
+        .take_while(|ancestor| *ancestor != root)
+        .map(|ancestor| ancestor.to_string_lossy().replace('\\', "/"))
+        .collect()
+}
+
+/// Whether a test host's command line has a path inside `dir`, e.g. its `--runtimeconfig` file
+fn mentions_dir(process: &ProcessInfo, dir: &str) -> bool {
+    process
+        .command
+        .replace('\\', "/")
+        .contains(&format!("{}/", dir.trim_end_matches('/')))
+}
+
+/// Pick the test host of the test project: the most recently listed one mentioning the closest of
+/// `test_dirs`. When none mentions them (e.g. an `artifacts` output layout), a lone candidate is
+/// still taken; without `test_dirs` the most recently listed candidate wins.
+fn select_test_host(candidates: &[ProcessInfo], test_dirs: &[String]) -> Option<ProcessInfo> {
+    if test_dirs.is_empty() {
+        return candidates.last().cloned();
+    }
+    test_dirs
+        .iter()
+        .find_map(|dir| candidates.iter().rfind(|process| mentions_dir(process, dir)))
+        .or(match candidates {
+            [process] => Some(process),
+            _ => None,
+        })
+        .cloned()
+}
+
+/// Wait for a test host launched with `VSTEST_HOST_DEBUG=1` and return its PID. Test hosts that
+/// were already running when the wait started belong to earlier runs and are ignored. `test_dir`
+/// is the test task's directory, used to tell apart the test hosts of several test projects;
+/// Windows only reports image names, so there the most recent new test host wins.
+pub fn wait_for_test_host(worktree: &Worktree, test_dir: Option<&str>) -> Result<u32> {
+    let existing: Vec<u32> = list_processes()?
+        .into_iter()
+        .filter(is_test_host)
+        .map(|process| process.pid)
+        .collect();
+    let (platform, _) = zed::current_platform();
+    let test_dirs = match test_dir {
+        Some(dir) if platform != zed::Os::Windows => test_dir_candidates(dir, &worktree.root_path()),
+        _ => Vec::new(),
+    };
+    debug_log!(worktree, "[csharp_roslyn] Waiting for a new test host (existing: {existing:?}, directories: {test_dirs:?})");
+
+    for _ in 0..TEST_HOST_WAIT_ATTEMPTS {
+        let candidates: Vec<ProcessInfo> = list_processes()?
+            .into_iter()
+            .filter(|process| is_test_host(process) && !existing.contains(&process.pid))
+            .collect();
+        if let Some(process) = select_test_host(&candidates, &test_dirs) {
+            debug_log!(worktree, "[csharp_roslyn] Found test host: {} ({})", process.pid, process.command);
+            return Ok(process.pid);
+        }
+        std::thread::sleep(TEST_HOST_POLL_INTERVAL);
+    }
+
+    Err("Timed out waiting for the test host. Start the debugger first, then run the test task \
+        with VSTEST_HOST_DEBUG=1 (the \"wait for debugger\" task) within 30 seconds."
+        .to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ps_output() {
+        let output = "    1 /sbin/init\n 4242 /usr/share/dotnet/dotnet exec --runtimeconfig App.Tests.runtimeconfig.json /home/me/.nuget/packages/microsoft.testplatform.testhost/17.9.0/lib/netcoreapp3.1/testhost.dll --port 40123\n";
+        let processes = parse_ps_output(output);
+        assert_eq!(processes.len(), 2);
+        assert_eq!(processes[1].pid, 4242);
+        assert!(is_test_host(&processes[1]));
+        assert!(!is_test_host(&processes[0]));
+    }
+
+    #[test]
+    fn test_parse_tasklist_output() {
+        let output = "\"System\",\"4\",\"Services\",\"0\",\"144 K\"\r\n\"testhost.exe\",\"9876\",\"Console\",\"1\",\"45,120 K\"\r\n";
+        let processes = parse_tasklist_output(output);
+        assert_eq!(
+            processes[1],
+            ProcessInfo { pid: 9876, command: "testhost.exe".to_string() }
+        );
+        assert!(is_test_host(&processes[1]));
+    }
+
+    #[test]
+    fn test_select_test_host() {
+        let host = |pid: u32, project: &str| ProcessInfo {
+            pid,
+            command: format!(
+                "/usr/share/dotnet/dotnet exec --runtimeconfig /src/tests/{project}/bin/Debug/net8.0/{project}.runtimeconfig.json /home/me/.nuget/packages/microsoft.testplatform.testhost/17.9.0/lib/netcoreapp3.1/testhost.dll"
+            ),
+        };
+        let candidates = [host(10, "Api.Tests"), host(20, "Core.Tests")];
+
+        let test_dirs = test_dir_candidates("/src/tests/Api.Tests/Controllers", "/src");
+        assert_eq!(test_dirs, ["/src/tests/Api.Tests/Controllers", "/src/tests/Api.Tests", "/src/tests"]);
+        assert_eq!(select_test_host(&candidates, &test_dirs).map(|process| process.pid), Some(10));
+
+        // No test host of this project yet: keep waiting rather than attach to another project's
+        let test_dirs = test_dir_candidates("/src/tests/Web.Tests", "/src/tests");
+        assert_eq!(test_dirs, ["/src/tests/Web.Tests"]);
+        assert_eq!(select_test_host(&candidates, &test_dirs), None);
+        assert_eq!(select_test_host(&candidates[..1], &test_dirs).map(|process| process.pid), Some(10));
+        assert_eq!(select_test_host(&candidates, &[]).map(|process| process.pid), Some(20));
+    }
+
+    #[test]
+    fn test_matches_process_name() {
+        let process = |command: &str| ProcessInfo { pid: 1, command: command.to_string() };
+        assert!(matches_process_name(&process("/app/bin/Debug/net8.0/MyApp --urls http://*:5000"), "MyApp"));
+        assert!(matches_process_name(&process("MyApp.exe"), "MyApp.exe"));
+        assert!(matches_process_name(&process("/usr/bin/dotnet bin/Debug/net8.0/MyApp.dll"), "MyApp"));
+        assert!(!matches_process_name(&process("/usr/bin/dotnet bin/Debug/net8.0/MyApp.Tests.dll"), "MyApp"));
+        assert!(!matches_process_name(&process("vim MyApp.dll"), "MyApp"));
+    }
+}
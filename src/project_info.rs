@@ -2,6 +2,7 @@ use std::path::{Path, PathBuf};
 use zed_extension_api as zed;
 
 use crate::logging::debug_log;
+use crate::settings::RoslynSettings;
 
 /// Lightweight parser for .csproj files to extract TargetFramework, AssemblyName, and OutputType.
 /// This is intentionally small and avoids heavy XML deps to stay WASM-friendly.
@@ -13,18 +14,50 @@ pub enum OutputType {
     Library,
 }
 
+/// Platform suffixes of target frameworks (`net8.0-android`) that run on Mono or a device
+/// runtime, which netcoredbg can't debug
+const MOBILE_PLATFORMS: &[&str] = &["android", "ios", "maccatalyst", "tvos", "tizen"];
+
+/// Whether a target framework is a mobile target (`net8.0-android`, `net8.0-ios17.0`, ...)
+/// rather than a desktop one (`net8.0`, `net8.0-windows10.0.19041.0`, `net8.0-macos`)
+pub fn is_mobile_target_framework(target_framework: &str) -> bool {
+    target_framework
+        .split_once('-')
+        .map(|(_, platform)| {
+            let platform = platform.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.').to_lowercase();
+            MOBILE_PLATFORMS.contains(&platform.as_str())
+        })
+        .unwrap_or(false)
+}
+
 #[derive(Debug, Clone)]
 pub struct DotNetProject {
     pub target_framework: String,
+    /// All frameworks from `TargetFrameworks`; just `target_framework` for single-targeted projects
+    pub target_frameworks: Vec<String>,
     pub assembly_name: String,
     pub output_type: OutputType,
     pub project_path: PathBuf,
+    /// Custom `OutputPath`, relative to the project directory (may contain `$(Configuration)`)
+    pub output_path: Option<String>,
+    pub append_target_framework: bool,
+    pub runtime_identifier: Option<String>,
+    pub append_runtime_identifier: bool,
+    /// `ArtifactsPath` when `UseArtifactsOutput` is enabled (or `ArtifactsPath` is set)
+    pub artifacts_path: Option<String>,
+    pub is_legacy: bool,
 }
 
 impl DotNetProject {
     pub fn from_csproj_text(text: &str, project_path: &Path) -> Self {
         let target_framework =
             extract_first_of_tags(text, &["TargetFramework", "TargetFrameworks"])
+                .or_else(|| {
+                    // Old-style projects declare e.g. <TargetFrameworkVersion>v4.7.2</TargetFrameworkVersion>
+                    extract_tag_value(text, "TargetFrameworkVersion").map(|version| {
+                        format!("net{}", version.trim_start_matches('v').replace('.', ""))
+                    })
+                })
                 .unwrap_or_else(|| {
                     // Fallback: Try to detect .NET SDK version or use latest LTS (net8.0)
                     // In practice, if TargetFramework is missing, the project is likely invalid,
@@ -46,45 +79,273 @@ impl DotNetProject {
             _ => OutputType::Library,
         };
 
-        // If TargetFrameworks (plural) was present, it may be a semicolon list; pick the first one.
-        let tf = if target_framework.contains(';') {
-            target_framework
-                .split(';')
-                .next()
-                .unwrap()
-                .trim()
-                .to_string()
-        } else {
-            target_framework
-        };
+        // If TargetFrameworks (plural) was present, it may be a semicolon list; default to the first one.
+        let target_frameworks: Vec<String> = target_framework
+            .split(';')
+            .map(|tf| tf.trim().to_string())
+            .filter(|tf| !tf.is_empty())
+            .collect();
+        let tf = target_frameworks.first().cloned().unwrap_or(target_framework);
+
+        let is_true = |tag: &str| extract_tag_value(text, tag).is_some_and(|v| v.eq_ignore_ascii_case("true"));
+        let is_not_false = |tag: &str| !extract_tag_value(text, tag).is_some_and(|v| v.eq_ignore_ascii_case("false"));
+        let artifacts_path = extract_tag_value(text, "ArtifactsPath")
+            .or_else(|| is_true("UseArtifactsOutput").then(|| "artifacts".to_string()));
 
         DotNetProject {
             target_framework: tf,
+            target_frameworks,
             assembly_name,
             output_type,
             project_path: project_path.to_path_buf(),
+            output_path: extract_tag_value(text, "OutputPath"),
+            append_target_framework: is_not_false("AppendTargetFrameworkToOutputPath"),
+            runtime_identifier: extract_tag_value(text, "RuntimeIdentifier"),
+            append_runtime_identifier: is_not_false("AppendRuntimeIdentifierToOutputPath"),
+            artifacts_path,
+            is_legacy: is_legacy_project(text),
         }
     }
 
-    /// Get the expected output path for a built assembly for the given configuration (Debug/Release).
-    pub fn get_output_path(&self, configuration: &str) -> PathBuf {
+    /// Target frameworks netcoredbg can debug, i.e. everything but mobile targets
+    pub fn debuggable_target_frameworks(&self) -> Vec<&str> {
+        self.target_frameworks
+            .iter()
+            .map(|tf| tf.as_str())
+            .filter(|tf| !is_mobile_target_framework(tf))
+            .collect()
+    }
+
+    /// Get the expected output path for a built assembly for the given configuration (Debug/Release)
+    /// and target framework, relative to the worktree. Follows the SDK's layouts:
+    /// `bin/<config>/<tfm>/<rid>/`, a custom `OutputPath`, and the artifacts layout
+    /// `<ArtifactsPath>/bin/<project>/<config>[_<tfm>][_<rid>]/`.
+    pub fn get_output_path(&self, configuration: &str, target_framework: &str) -> PathBuf {
+        // .NET Framework executables are run directly; everything else is launched through the dll
         let ext = match self.output_type {
-            OutputType::Exe | OutputType::WinExe => "exe",
-            OutputType::Library => "dll",
+            OutputType::Exe | OutputType::WinExe if self.is_legacy => "exe",
+            _ => "dll",
         };
+        let file_name = format!("{}.{}", &self.assembly_name, ext);
 
         let project_dir = self
             .project_path
             .parent()
             .map(|p| p.to_path_buf())
             .unwrap_or_else(|| PathBuf::from("."));
+        let rid = self
+            .runtime_identifier
+            .as_deref()
+            .filter(|_| self.append_runtime_identifier && !self.is_legacy);
+
+        if let Some(artifacts_path) = &self.artifacts_path {
+            let project_name = self
+                .project_path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or(&self.assembly_name);
+            // The pivot only includes the framework when the project targets several
+            let mut pivot = configuration.to_lowercase();
+            if self.target_frameworks.len() > 1 {
+                pivot.push('_');
+                pivot.push_str(target_framework);
+            }
+            if let Some(rid) = rid {
+                pivot.push('_');
+                pivot.push_str(rid);
+            }
+            return project_dir
+                .join(normalize_msbuild_path(artifacts_path))
+                .join("bin")
+                .join(project_name)
+                .join(pivot)
+                .join(file_name);
+        }
+
+        let mut output_dir = match &self.output_path {
+            Some(output_path) if !output_path.contains("$(") || output_path.contains("$(Configuration)") => {
+                let output_path = output_path
+                    .replace("$(Configuration)", configuration)
+                    .replace("$(TargetFramework)", target_framework);
+                project_dir.join(normalize_msbuild_path(&output_path))
+            }
+            _ => project_dir.join("bin").join(configuration),
+        };
+        // Legacy projects never append the framework to the output path
+        if self.append_target_framework && !self.is_legacy && !self.output_path_contains_framework() {
+            output_dir = output_dir.join(target_framework);
+        }
+        if let Some(rid) = rid {
+            output_dir = output_dir.join(rid);
+        }
 
-        project_dir
-            .join("bin")
-            .join(configuration)
-            .join(&self.target_framework)
-            .join(format!("{}.{}", &self.assembly_name, ext))
+        output_dir.join(file_name)
     }
+
+    fn output_path_contains_framework(&self) -> bool {
+        self.output_path
+            .as_deref()
+            .is_some_and(|path| path.contains("$(TargetFramework)"))
+    }
+}
+
+/// Append the `Directory.Build.props` files MSBuild imports for a project to its text, so
+/// properties defined there (e.g. a shared `TargetFramework`) are picked up. The project's
+/// own properties come first and therefore win, since the tag extractor takes the first match.
+pub fn with_directory_build_props(worktree: &zed::Worktree, project_path: &str, text: &str) -> String {
+    let project_dir = Path::new(project_path).parent().unwrap_or(Path::new(""));
+    let mut imported = Vec::new();
+
+    // MSBuild imports the nearest Directory.Build.props; it only continues upward when that
+    // file explicitly imports the one above it.
+    for (depth, dir) in project_dir.ancestors().enumerate() {
+        let props_path = dir.join("Directory.Build.props").to_string_lossy().replace('\\', "/");
+        let Ok(props_text) = worktree.read_text_file(&props_path) else {
+            continue;
+        };
+        debug_log!(worktree, "[csharp_roslyn] Importing {props_path} for {project_path}");
+        let imports_parent = props_text.contains("GetPathOfFileAbove") || props_text.contains("GetDirectoryNameOfFileAbove");
+        imported.push(("../".repeat(depth), props_text));
+        if !imports_parent {
+            break;
+        }
+    }
+
+    merge_imported_props(text, &imported)
+}
+
+/// Merge imported props after the project text. `$(MSBuildThisFileDirectory)` in each props file
+/// is rewritten relative to the project directory (`dir_prefix`, e.g. `../../`).
+fn merge_imported_props(text: &str, imported: &[(String, String)]) -> String {
+    let mut merged = text.to_string();
+    for (dir_prefix, props_text) in imported {
+        merged.push('\n');
+        merged.push_str(&props_text.replace("$(MSBuildThisFileDirectory)", dir_prefix));
+    }
+    merged
+}
+
+/// Convert an MSBuild path (backslashes, trailing separator) to a relative `Path`
+fn normalize_msbuild_path(path: &str) -> PathBuf {
+    PathBuf::from(path.replace('\\', "/").trim_end_matches('/'))
+}
+
+/// Detect an old-style (non-SDK) project file. SDK-style projects declare `Sdk="..."` on the
+/// root element or import an SDK explicitly; legacy ones use `ToolsVersion` and the 2003 MSBuild namespace.
+pub fn is_legacy_project(text: &str) -> bool {
+    let Some(start) = text.find("<Project") else {
+        return false;
+    };
+    let root_element = &text[start..start + text[start..].find('>').unwrap_or(0)];
+    if root_element.contains("Sdk=") || text.contains("<Sdk ") {
+        return false;
+    }
+
+    root_element.contains("ToolsVersion=")
+        || root_element.contains("http://schemas.microsoft.com/developer/msbuild/2003")
+        || text.contains("<TargetFrameworkVersion>")
+}
+
+/// Detect an ASP.NET Core project (`<Project Sdk="Microsoft.NET.Sdk.Web">`)
+pub fn is_web_project(text: &str) -> bool {
+    text.contains("Microsoft.NET.Sdk.Web")
+}
+
+/// The project's `UserSecretsId`, if it uses `dotnet user-secrets`
+pub fn user_secrets_id(text: &str) -> Option<String> {
+    extract_tag_value(text, "UserSecretsId").filter(|id| !id.is_empty())
+}
+
+/// Parse `dotnet user-secrets list` output (`Key = Value` per line) into environment variables.
+/// Configuration sections are separated by `__` in environment variable names.
+pub fn parse_user_secrets_list(output: &str) -> Vec<(String, String)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(" = ")?;
+            let key = key.trim();
+            (!key.is_empty() && !key.contains(' ')).then(|| (key.replace(':', "__"), value.to_string()))
+        })
+        .collect()
+}
+
+/// Detect a worker service project (`<Project Sdk="Microsoft.NET.Sdk.Worker">`)
+pub fn is_worker_project(text: &str) -> bool {
+    text.contains("Microsoft.NET.Sdk.Worker")
+}
+
+/// The `Project` launch profile from `Properties/launchSettings.json`, which `dotnet run` applies
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LaunchProfile {
+    pub application_url: Option<String>,
+    pub environment_variables: Vec<(String, String)>,
+}
+
+/// Pick the launch profile `dotnet run` would use: the one named after the project if it runs
+/// the project directly, otherwise the first `"commandName": "Project"` profile.
+pub fn parse_launch_settings(text: &str, project_name: &str) -> Option<LaunchProfile> {
+    let settings: serde_json::Value = serde_json::from_str(text).ok()?;
+    let profiles = settings.get("profiles")?.as_object()?;
+    let runs_project =
+        |profile: &serde_json::Value| profile.get("commandName").and_then(|v| v.as_str()) == Some("Project");
+    let profile = profiles
+        .get(project_name)
+        .filter(|profile| runs_project(profile))
+        .or_else(|| profiles.values().find(|profile| runs_project(profile)))?;
+
+    let environment_variables = profile
+        .get("environmentVariables")
+        .and_then(|vars| vars.as_object())
+        .map(|vars| {
+            vars.iter()
+                .filter_map(|(key, value)| Some((key.clone(), value.as_str()?.to_string())))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Some(LaunchProfile {
+        application_url: profile
+            .get("applicationUrl")
+            .and_then(|v| v.as_str())
+            .map(|url| url.to_string()),
+        environment_variables,
+    })
+}
+
+/// Extract C# project paths from solution file text. Supports classic `.sln` files
+/// (`Project("{...}") = "Name", "path\\Name.csproj", "{...}"`) and XML `.slnx` files.
+/// Paths are returned with forward slashes, relative to the solution directory.
+pub fn parse_solution_projects(text: &str) -> Vec<String> {
+    let mut projects = Vec::new();
+
+    for line in text.lines() {
+        let line = line.trim();
+        if line.starts_with("Project(") {
+            // The second quoted value after '=' is the project path
+            if let Some((_, rest)) = line.split_once('=') {
+                if let Some(path) = rest.split('"').nth(3) {
+                    if path.ends_with(".csproj") {
+                        projects.push(path.replace('\\', "/"));
+                    }
+                }
+            }
+        } else if line.starts_with("<Project ") {
+            if let Some(path) = extract_attribute(line, "Path") {
+                if path.ends_with(".csproj") {
+                    projects.push(path.replace('\\', "/"));
+                }
+            }
+        }
+    }
+
+    projects
+}
+
+fn extract_attribute(element: &str, name: &str) -> Option<String> {
+    let prefix = format!("{}=\"", name);
+    let start = element.find(&prefix)? + prefix.len();
+    let end = element[start..].find('"')?;
+    Some(element[start..start + end].to_string())
 }
 
 fn extract_first_of_tags(text: &str, tags: &[&str]) -> Option<String> {
@@ -126,6 +387,33 @@ fn extract_tag_value(text: &str, tag: &str) -> Option<String> {
     None
 }
 
+/// Check whether a solution (worktree-relative path) contains any old-style .NET Framework projects
+pub fn solution_has_legacy_projects(worktree: &zed::Worktree, solution_path: &str) -> bool {
+    solution_project_paths(worktree, solution_path).iter().any(|project_path| {
+        let is_legacy = worktree
+            .read_text_file(project_path)
+            .map(|text| is_legacy_project(&text))
+            .unwrap_or(false);
+        if is_legacy {
+            debug_log!(worktree, "[csharp_roslyn] Legacy .NET Framework project detected: {project_path}");
+        }
+        is_legacy
+    })
+}
+
+/// Worktree-relative paths of the projects in a worktree-relative solution
+pub fn solution_project_paths(worktree: &zed::Worktree, solution_path: &str) -> Vec<String> {
+    let Ok(text) = worktree.read_text_file(solution_path) else {
+        debug_log!(worktree, "[csharp_roslyn] Could not read solution {solution_path}");
+        return Vec::new();
+    };
+    let solution_dir = Path::new(solution_path).parent().unwrap_or(Path::new(""));
+    parse_solution_projects(&text)
+        .iter()
+        .map(|project| solution_dir.join(project).to_string_lossy().replace('\\', "/"))
+        .collect()
+}
+
 /// Check if the given path is a Unity project by looking for characteristic Unity directories and files.
 /// A Unity project is identified by:
 /// 1. Assets/ directory exists (checked by reading AssemblyDefinitions.json if available)
@@ -164,7 +452,7 @@ pub fn is_unity_project(worktree: &zed::Worktree) -> bool {
 /// - Assembly-CSharp.csproj and other .csproj files
 /// 
 /// Returns either a path to existing .sln file or instructions for generation.
-pub fn ensure_unity_project_files(worktree: &zed::Worktree) -> Result<String, String> {
+pub fn ensure_unity_project_files(worktree: &zed::Worktree, settings: &RoslynSettings) -> Result<String, String> {
     if !is_unity_project(worktree) {
         return Err("Not a Unity project".to_string());
     }
@@ -186,7 +474,23 @@ pub fn ensure_unity_project_files(worktree: &zed::Worktree) -> Result<String, St
         }
     }
     
-    // No .sln found - provide helpful instructions
+    // No .sln found - if enabled, try generating one with a Unity Editor in batch mode. This
+    // blocks startup until the editor exits, so it's opt-in.
+    if settings.generate_unity_project_files {
+        match generate_unity_project_files(worktree, settings) {
+            Ok(()) => {
+                for sln_name in &common_sln_names {
+                    if worktree.read_text_file(sln_name).is_ok() {
+                        debug_log!(worktree, "[csharp_roslyn] Generated Unity solution: {sln_name}");
+                        return Ok(sln_name.to_string());
+                    }
+                }
+            }
+            Err(e) => debug_log!(worktree, "[csharp_roslyn] Unity project file generation failed: {e}"),
+        }
+    }
+
+    // Still no .sln - provide helpful instructions
     let instructions = format!(
         "Unity project detected at '{}' but no .sln files found.\n\
         \n\
@@ -198,6 +502,9 @@ pub fn ensure_unity_project_files(worktree: &zed::Worktree) -> Result<String, St
         Alternative: Run the helper script from your terminal:\n\
         scripts/generate-unity-projects.sh\n\
         \n\
+        Or set \"generateUnityProjectFiles\": true to generate them with the Unity Hub install of\n\
+        the Editor version in ProjectSettings/ProjectVersion.txt (or with \"unityEditorPath\").\n\
+        \n\
         Once generated, the .sln file will be detected automatically.",
         root_path
     );
@@ -207,6 +514,99 @@ pub fn ensure_unity_project_files(worktree: &zed::Worktree) -> Result<String, St
     Err(instructions)
 }
 
+/// Parse the editor version (`m_EditorVersion: 2022.3.10f1`) from `ProjectSettings/ProjectVersion.txt`
+pub fn parse_unity_editor_version(text: &str) -> Option<String> {
+    text.lines()
+        .find_map(|line| line.trim().strip_prefix("m_EditorVersion:"))
+        .map(|version| version.trim().to_string())
+        .filter(|version| !version.is_empty())
+}
+
+/// Default Unity Hub install locations of an editor version's executable
+pub fn unity_editor_candidates(platform: zed::Os, version: &str, home: Option<&str>) -> Vec<String> {
+    match platform {
+        zed::Os::Windows => vec![format!("C:\\Program Files\\Unity\\Hub\\Editor\\{version}\\Editor\\Unity.exe")],
+        zed::Os::Mac => vec![format!("/Applications/Unity/Hub/Editor/{version}/Unity.app/Contents/MacOS/Unity")],
+        zed::Os::Linux => home
+            .map(|home| vec![format!("{home}/Unity/Hub/Editor/{version}/Editor/Unity")])
+            .unwrap_or_default(),
+    }
+}
+
+/// Generate the solution and project files by running the Unity Editor in batch mode
+/// (`-executeMethod UnityEditor.SyncVS.SyncSolution`).
+///
+/// The editor is `unityEditorPath` from settings, or the Hub install matching the project's
+/// editor version. Unity refuses to open a project that's already open in another editor, in
+/// which case the running editor is expected to keep the files up to date.
+fn generate_unity_project_files(worktree: &zed::Worktree, settings: &RoslynSettings) -> Result<(), String> {
+    let candidates = match &settings.unity_editor_path {
+        Some(path) => vec![path.clone()],
+        None => {
+            let version = worktree
+                .read_text_file("ProjectSettings/ProjectVersion.txt")
+                .ok()
+                .and_then(|text| parse_unity_editor_version(&text))
+                .ok_or_else(|| "Unity editor version not found in ProjectSettings/ProjectVersion.txt".to_string())?;
+            let (platform, _) = zed::current_platform();
+            let home = worktree
+                .shell_env()
+                .into_iter()
+                .find(|(key, _)| key == "HOME")
+                .map(|(_, value)| value);
+            unity_editor_candidates(platform, &version, home.as_deref())
+        }
+    };
+
+    let root_path = worktree.root_path();
+    for editor in &candidates {
+        debug_log!(worktree, "[csharp_roslyn] Generating Unity project files with {editor}");
+        let Ok(output) = zed::process::Command::new(editor)
+            .args([
+                "-batchmode",
+                "-nographics",
+                "-quit",
+                "-projectPath",
+                root_path.as_str(),
+                "-executeMethod",
+                "UnityEditor.SyncVS.SyncSolution",
+            ])
+            .output()
+        else {
+            continue;
+        };
+        if output.status == Some(0) {
+            return Ok(());
+        }
+        return Err(format!(
+            "Unity batch mode exited with {:?}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Err(format!("No Unity Editor found (tried {})", candidates.join(", ")))
+}
+
+/// Check for a Godot .NET project, identified by `project.godot` at the worktree root
+pub fn is_godot_project(worktree: &zed::Worktree) -> bool {
+    worktree.read_text_file("project.godot").is_ok()
+}
+
+/// Godot-specific OmniSharp defaults: the editor's `.godot/` cache holds generated and imported
+/// files that shouldn't be analyzed
+pub fn get_godot_omnisharp_config() -> serde_json::Value {
+    serde_json::json!({
+        "FileOptions": {
+            "excludeSearchPatterns": [
+                "**/.godot/**",
+                "**/obj/**",
+                "**/bin/**"
+            ]
+        }
+    })
+}
+
 /// Generate Unity-specific OmniSharp configuration defaults
 pub fn get_unity_omnisharp_config() -> serde_json::Value {
     serde_json::json!({
@@ -238,6 +638,24 @@ pub fn get_unity_omnisharp_config() -> serde_json::Value {
     })
 }
 
+/// Solutions with more projects than this use the large solution profile by default
+pub const LARGE_SOLUTION_PROJECT_COUNT: usize = 50;
+
+/// OmniSharp defaults for very large solutions: analyzers only run on open documents, and the
+/// features that scan every project on each keystroke (import completion, inlay hints) are off
+pub fn get_large_solution_omnisharp_config() -> serde_json::Value {
+    serde_json::json!({
+        "RoslynExtensionsOptions": {
+            "analyzeOpenDocumentsOnly": true,
+            "enableImportCompletion": false,
+            "InlayHintsOptions": {
+                "EnableForParameters": false,
+                "EnableForTypes": false
+            }
+        }
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -310,9 +728,181 @@ mod tests {
         let project = DotNetProject::from_csproj_text(csproj_content, project_path);
         
         assert_eq!(project.target_framework, "net6.0"); // first one
+        assert_eq!(project.target_frameworks, vec!["net6.0", "net8.0", "net462"]);
         assert_eq!(project.assembly_name, "MultiTargetApp");
     }
 
+    #[test]
+    fn test_output_path_layouts() {
+        let project = |props: &str| {
+            let text = format!(
+                "<Project Sdk=\"Microsoft.NET.Sdk\"><PropertyGroup><OutputType>Exe</OutputType>{props}</PropertyGroup></Project>"
+            );
+            DotNetProject::from_csproj_text(&text, Path::new("src/App/App.csproj"))
+        };
+        let output = |project: DotNetProject, tf: &str| project.get_output_path("Debug", tf);
+
+        assert_eq!(
+            output(project("<TargetFramework>net8.0</TargetFramework>"), "net8.0"),
+            PathBuf::from("src/App/bin/Debug/net8.0/App.dll")
+        );
+        assert_eq!(
+            output(project("<TargetFramework>net8.0</TargetFramework><RuntimeIdentifier>linux-x64</RuntimeIdentifier>"), "net8.0"),
+            PathBuf::from("src/App/bin/Debug/net8.0/linux-x64/App.dll")
+        );
+        assert_eq!(
+            output(project("<OutputPath>..\\..\\out\\$(Configuration)\\</OutputPath><AppendTargetFrameworkToOutputPath>false</AppendTargetFrameworkToOutputPath>"), "net8.0"),
+            PathBuf::from("src/App/../../out/Debug/App.dll")
+        );
+        assert_eq!(
+            output(project("<TargetFrameworks>net8.0;net9.0</TargetFrameworks><UseArtifactsOutput>true</UseArtifactsOutput>"), "net9.0"),
+            PathBuf::from("src/App/artifacts/bin/App/debug_net9.0/App.dll")
+        );
+    }
+
+    #[test]
+    fn test_merge_imported_props() {
+        let csproj = r#"<Project Sdk="Microsoft.NET.Sdk"><PropertyGroup><OutputType>Exe</OutputType></PropertyGroup></Project>"#;
+        let props = r#"<Project>
+  <PropertyGroup>
+    <TargetFramework>net9.0</TargetFramework>
+    <OutputType>Library</OutputType>
+    <ArtifactsPath>$(MSBuildThisFileDirectory)artifacts</ArtifactsPath>
+  </PropertyGroup>
+</Project>"#;
+        let merged = merge_imported_props(csproj, &[("../../".to_string(), props.to_string())]);
+        let project = DotNetProject::from_csproj_text(&merged, Path::new("src/App/App.csproj"));
+
+        assert_eq!(project.target_framework, "net9.0");
+        assert!(matches!(project.output_type, OutputType::Exe)); // the project overrides props
+        assert_eq!(
+            project.get_output_path("Debug", "net9.0"),
+            PathBuf::from("src/App/../../artifacts/bin/App/debug/App.dll")
+        );
+    }
+
+    #[test]
+    fn test_legacy_project_detection() {
+        let legacy = r#"<?xml version="1.0" encoding="utf-8"?>
+<Project ToolsVersion="15.0" xmlns="http://schemas.microsoft.com/developer/msbuild/2003">
+  <PropertyGroup>
+    <OutputType>WinExe</OutputType>
+    <TargetFrameworkVersion>v4.7.2</TargetFrameworkVersion>
+  </PropertyGroup>
+</Project>
+"#;
+        let project = DotNetProject::from_csproj_text(legacy, std::path::Path::new("Legacy.csproj"));
+        assert!(is_legacy_project(legacy));
+        assert_eq!(project.target_framework, "net472");
+        assert_eq!(project.get_output_path("Debug", "net472"), PathBuf::from("bin/Debug/Legacy.exe"));
+
+        let sdk_style = r#"<Project Sdk="Microsoft.NET.Sdk"><PropertyGroup><TargetFramework>net48</TargetFramework></PropertyGroup></Project>"#;
+        assert!(!is_legacy_project(sdk_style));
+    }
+
+    #[test]
+    fn test_parse_launch_settings() {
+        let launch_settings = r#"{
+  "profiles": {
+    "IIS Express": { "commandName": "IISExpress" },
+    "WebApi": {
+      "commandName": "Project",
+      "applicationUrl": "https://localhost:7001;http://localhost:5001",
+      "environmentVariables": { "ASPNETCORE_ENVIRONMENT": "Development" }
+    }
+  }
+}"#;
+        let profile = parse_launch_settings(launch_settings, "WebApi").unwrap();
+        assert_eq!(profile.application_url.as_deref(), Some("https://localhost:7001;http://localhost:5001"));
+        assert_eq!(
+            profile.environment_variables,
+            vec![("ASPNETCORE_ENVIRONMENT".to_string(), "Development".to_string())]
+        );
+
+        // Falls back to the first profile that runs the project
+        assert!(parse_launch_settings(launch_settings, "Other").is_some());
+        assert!(parse_launch_settings(r#"{ "profiles": { "IIS Express": { "commandName": "IISExpress" } } }"#, "WebApi").is_none());
+        assert!(is_web_project(r#"<Project Sdk="Microsoft.NET.Sdk.Web"></Project>"#));
+        assert!(is_worker_project(r#"<Project Sdk="Microsoft.NET.Sdk.Worker"></Project>"#));
+        assert_eq!(
+            user_secrets_id("<PropertyGroup><UserSecretsId>aspnet-App-1234</UserSecretsId></PropertyGroup>"),
+            Some("aspnet-App-1234".to_string())
+        );
+        assert!(!is_worker_project(r#"<Project Sdk="Microsoft.NET.Sdk.Web"></Project>"#));
+    }
+
+    #[test]
+    fn test_parse_user_secrets_list() {
+        let output = "ConnectionStrings:Default = Server=localhost;Password=a = b\nApiKey = secret\n";
+        assert_eq!(
+            parse_user_secrets_list(output),
+            vec![
+                ("ConnectionStrings__Default".to_string(), "Server=localhost;Password=a = b".to_string()),
+                ("ApiKey".to_string(), "secret".to_string()),
+            ]
+        );
+        assert!(parse_user_secrets_list("No secrets configured for this application.").is_empty());
+    }
+
+    #[test]
+    fn test_parse_solution_projects() {
+        let sln = r#"
+Microsoft Visual Studio Solution File, Format Version 12.00
+Project("{FAE04EC0-301F-11D3-BF4B-00C04F79EFBC}") = "App", "src\App\App.csproj", "{11111111-1111-1111-1111-111111111111}"
+EndProject
+Project("{2150E333-8FDC-42A3-9474-1A3956D46DE8}") = "Solution Items", "Solution Items", "{22222222-2222-2222-2222-222222222222}"
+EndProject
+"#;
+        assert_eq!(parse_solution_projects(sln), vec!["src/App/App.csproj"]);
+
+        let slnx = r#"<Solution>
+  <Project Path="src/Api/Api.csproj" />
+  <Project Path="tests/Api.Tests/Api.Tests.csproj" />
+</Solution>"#;
+        assert_eq!(
+            parse_solution_projects(slnx),
+            vec!["src/Api/Api.csproj", "tests/Api.Tests/Api.Tests.csproj"]
+        );
+    }
+
+    #[test]
+    fn test_parse_unity_editor_version() {
+        let text = "m_EditorVersion: 2022.3.10f1\nm_EditorVersionWithRevision: 2022.3.10f1 (ff3792e53c62)\n";
+        assert_eq!(parse_unity_editor_version(text), Some("2022.3.10f1".to_string()));
+        assert_eq!(parse_unity_editor_version(""), None);
+
+        assert_eq!(
+            unity_editor_candidates(zed::Os::Linux, "2022.3.10f1", Some("/home/me")),
+            vec!["/home/me/Unity/Hub/Editor/2022.3.10f1/Editor/Unity".to_string()]
+        );
+        assert!(unity_editor_candidates(zed::Os::Linux, "2022.3.10f1", None).is_empty());
+    }
+
+    #[test]
+    fn test_mobile_target_frameworks() {
+        assert!(is_mobile_target_framework("net8.0-android"));
+        assert!(is_mobile_target_framework("net8.0-ios17.0"));
+        assert!(is_mobile_target_framework("net8.0-maccatalyst"));
+        assert!(!is_mobile_target_framework("net8.0"));
+        assert!(!is_mobile_target_framework("net8.0-windows10.0.19041.0"));
+        assert!(!is_mobile_target_framework("net8.0-macos"));
+
+        let text = r#"<Project Sdk="Microsoft.NET.Sdk">
+  <PropertyGroup>
+    <TargetFrameworks>net8.0-android;net8.0-ios;net8.0-maccatalyst;net8.0-windows10.0.19041.0</TargetFrameworks>
+  </PropertyGroup>
+</Project>"#;
+        let project = DotNetProject::from_csproj_text(text, Path::new("App/App.csproj"));
+        assert_eq!(project.debuggable_target_frameworks(), vec!["net8.0-windows10.0.19041.0"]);
+    }
+
+    #[test]
+    fn test_godot_omnisharp_config_excludes_editor_cache() {
+        let config = get_godot_omnisharp_config();
+        let patterns = config["FileOptions"]["excludeSearchPatterns"].as_array().unwrap();
+        assert!(patterns.iter().any(|p| p.as_str() == Some("**/.godot/**")));
+    }
+
     #[test]
     fn test_unity_omnisharp_config_structure() {
         let config = get_unity_omnisharp_config();
@@ -0,0 +1,226 @@
+use zed_extension_api::{
+    Result, SlashCommandArgumentCompletion, SlashCommandOutput, SlashCommandOutputSection, Worktree,
+};
+
+use crate::logging::{self, debug_log};
+use crate::processes::command_on_path;
+use crate::settings::RoslynSettings;
+
+/// Common `dotnet new` template short names offered as completions
+const COMMON_TEMPLATES: &[(&str, &str)] = &[
+    ("console", "Console App"),
+    ("classlib", "Class Library"),
+    ("web", "ASP.NET Core Empty"),
+    ("webapi", "ASP.NET Core Web API"),
+    ("mvc", "ASP.NET Core Web App (MVC)"),
+    ("blazor", "Blazor Web App"),
+    ("worker", "Worker Service"),
+    ("xunit", "xUnit Test Project"),
+    ("nunit", "NUnit 3 Test Project"),
+    ("mstest", "MSTest Test Project"),
+];
+
+/// Complete the template argument of `/dotnet-new`
+pub fn complete_template_argument(args: &[String]) -> Vec<SlashCommandArgumentCompletion> {
+    if args.len() > 1 {
+        return Vec::new();
+    }
+    let query = args.first().map(|arg| arg.as_str()).unwrap_or_default();
+    COMMON_TEMPLATES
+        .iter()
+        .filter(|(name, _)| name.starts_with(query))
+        .map(|(name, description)| SlashCommandArgumentCompletion {
+            label: format!("{name} ({description})"),
+            new_text: format!("{name} "),
+            run_command: false,
+        })
+        .collect()
+}
+
+/// Validate a project name used as both `-n` and the output directory, so it can't escape the worktree
+pub fn is_valid_project_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+}
+
+/// Run a dotnet CLI command, returning stdout or an error carrying its output
+pub(crate) fn run_dotnet(worktree: &Worktree, args: &[String]) -> Result<String> {
+    let output = command_on_path(worktree, "dotnet")
+        .args(args.iter().cloned())
+        .output()
+        .map_err(|e| format!("Failed to run dotnet {}: {e}", args.join(" ")))?;
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    if output.status != Some(0) {
+        return Err(format!(
+            "dotnet {} failed:\n{}{}",
+            args.join(" "),
+            stdout,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    Ok(stdout)
+}
+
+/// Absolute path of the solution configured in settings, if any
+pub(crate) fn configured_solution(worktree: &Worktree, settings: &RoslynSettings) -> Option<String> {
+    let solution = settings
+        .solution
+        .clone()
+        .filter(|solution| !solution.starts_with("file://"))?;
+
+    if solution.starts_with('/') || solution.chars().nth(1) == Some(':') {
+        return Some(solution);
+    }
+    let root = worktree.root_path();
+    let sep = if root.contains('\\') { '\\' } else { '/' };
+    Some(format!("{}{sep}{solution}", root.trim_end_matches(['/', '\\'])))
+}
+
+/// Resolve a worktree-relative project path to an absolute one, checking that it exists
+pub(crate) fn resolve_project(worktree: &Worktree, project: &str) -> Result<String> {
+    if !project.ends_with(".csproj") || project.split(['/', '\\']).any(|part| part == "..") {
+        return Err(format!("Expected a .csproj path inside the worktree, got '{project}'"));
+    }
+    worktree
+        .read_text_file(project)
+        .map_err(|_| format!("Project not found: {project}"))?;
+    let root = worktree.root_path();
+    let sep = if root.contains('\\') { '\\' } else { '/' };
+    Ok(format!("{}{sep}{project}", root.trim_end_matches(['/', '\\'])))
+}
+
+/// Slash command output shown as one collapsible section
+pub(crate) fn single_section(text: String, label: String) -> SlashCommandOutput {
+    SlashCommandOutput {
+        sections: vec![SlashCommandOutputSection {
+            range: (0..text.len()).into(),
+            label,
+        }],
+        text,
+    }
+}
+
+/// Run `/dotnet-new`. Without arguments, lists the installed templates. With
+/// `<template> <name>`, creates the project in `<worktree>/<name>` and adds it to the
+/// configured solution, if any.
+pub fn run_dotnet_new(args: &[String], worktree: Option<&Worktree>) -> Result<SlashCommandOutput> {
+    let worktree = worktree.ok_or_else(|| "/dotnet-new needs an open worktree".to_string())?;
+    let settings = RoslynSettings::for_worktree(worktree);
+    let _log = logging::scope(worktree, &settings);
+    worktree
+        .which("dotnet")
+        .ok_or_else(|| "dotnet was not found on PATH".to_string())?;
+
+    let (template, name) = match args {
+        [] => {
+            let templates = run_dotnet(worktree, &["new".to_string(), "list".to_string()])?;
+            return Ok(single_section(templates, "dotnet new templates".to_string()));
+        }
+        [template, name] => (template, name),
+        _ => return Err("Usage: /dotnet-new <template> <ProjectName>".to_string()),
+    };
+    if !is_valid_project_name(name) {
+        return Err(format!("Invalid project name '{name}': use letters, digits, '.', '_' and '-'"));
+    }
+
+    let root = worktree.root_path();
+    let sep = if root.contains('\\') { '\\' } else { '/' };
+    let output_dir = format!("{}{sep}{name}", root.trim_end_matches(['/', '\\']));
+    debug_log!(worktree, "[csharp_roslyn] dotnet new {template} -n {name} -o {output_dir}");
+    let mut text = run_dotnet(
+        worktree,
+        &[
+            "new".to_string(),
+            template.clone(),
+            "-n".to_string(),
+            name.clone(),
+            "-o".to_string(),
+            output_dir.clone(),
+        ],
+    )?;
+
+    let project_file = format!("{output_dir}{sep}{name}.csproj");
+    if let Some(solution_path) = configured_solution(worktree, &settings) {
+        text.push_str(&run_dotnet(
+            worktree,
+            &["sln".to_string(), solution_path, "add".to_string(), project_file],
+        )?);
+    }
+
+    Ok(single_section(text, format!("dotnet new {template} {name}")))
+}
+
+/// Run `/dotnet-sln add|remove <Project.csproj>` against the configured solution.
+/// OmniSharp watches the solution file and reloads it after the change.
+pub fn run_dotnet_sln(args: &[String], worktree: Option<&Worktree>) -> Result<SlashCommandOutput> {
+    let worktree = worktree.ok_or_else(|| "/dotnet-sln needs an open worktree".to_string())?;
+    let settings = RoslynSettings::for_worktree(worktree);
+    let _log = logging::scope(worktree, &settings);
+    let (subcommand, project) = match args {
+        [subcommand, project] if subcommand == "add" || subcommand == "remove" => (subcommand, project),
+        _ => return Err("Usage: /dotnet-sln add|remove <Project.csproj>".to_string()),
+    };
+    let solution = configured_solution(worktree, &settings)
+        .ok_or_else(|| "No solution configured; set \"solution\" in the omnisharp-roslyn initialization_options".to_string())?;
+    worktree
+        .which("dotnet")
+        .ok_or_else(|| "dotnet was not found on PATH".to_string())?;
+
+    let project_path = resolve_project(worktree, project)?;
+    debug_log!(worktree, "[csharp_roslyn] dotnet sln {solution} {subcommand} {project_path}");
+    let text = run_dotnet(
+        worktree,
+        &["sln".to_string(), solution, subcommand.clone(), project_path],
+    )?;
+    Ok(single_section(text, format!("dotnet sln {subcommand} {project}")))
+}
+
+/// Complete the subcommand of `/dotnet-sln`
+pub fn complete_sln_argument(args: &[String]) -> Vec<SlashCommandArgumentCompletion> {
+    if args.len() > 1 {
+        return Vec::new();
+    }
+    let query = args.first().map(|arg| arg.as_str()).unwrap_or_default();
+    ["add", "remove"]
+        .iter()
+        .filter(|subcommand| subcommand.starts_with(query))
+        .map(|subcommand| SlashCommandArgumentCompletion {
+            label: subcommand.to_string(),
+            new_text: format!("{subcommand} "),
+            run_command: false,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_valid_project_name() {
+        assert!(is_valid_project_name("MyApp.Api"));
+        assert!(is_valid_project_name("my-app_2"));
+        assert!(!is_valid_project_name("../escape"));
+        assert!(!is_valid_project_name("a/b"));
+        assert!(!is_valid_project_name(""));
+    }
+
+    #[test]
+    fn test_complete_template_argument() {
+        let completions = complete_template_argument(&["web".to_string()]);
+        let names: Vec<_> = completions.iter().map(|c| c.new_text.as_str()).collect();
+        assert_eq!(names, vec!["web ", "webapi "]);
+        assert!(complete_template_argument(&["console".to_string(), "App".to_string()]).is_empty());
+    }
+
+    #[test]
+    fn test_complete_sln_argument() {
+        let completions = complete_sln_argument(&["r".to_string()]);
+        assert_eq!(completions.len(), 1);
+        assert_eq!(completions[0].new_text, "remove ");
+        assert!(complete_sln_argument(&["add".to_string(), "App".to_string()]).is_empty());
+    }
+}
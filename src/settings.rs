@@ -0,0 +1,187 @@
+use std::collections::BTreeMap;
+use serde::Deserialize;
+use zed_extension_api::{settings::LspSettings, Worktree};
+
+use crate::debugger::DebugAdapterKind;
+
+/// Settings key the extension's options live under (`lsp.omnisharp-roslyn.initialization_options`)
+pub const SETTINGS_KEY: &str = "omnisharp-roslyn";
+/// Older settings key, still read for the logging options
+const LEGACY_SETTINGS_KEY: &str = "csharp_roslyn";
+/// Renamed keys, as (old, new)
+const MIGRATED_KEYS: &[(&str, &str)] = &[
+    ("enable_debug_logging", "enableDebugLogging"),
+    ("unsafe_full_logs", "unsafeFullLogs"),
+];
+
+/// The extension's settings, read from the `initialization_options` of the `omnisharp-roslyn`
+/// language server. Zed also forwards these options to OmniSharp, so PascalCase sections such
+/// as `RoslynExtensionsOptions` are OmniSharp's own and pass through untouched.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RoslynSettings {
+    // Language server
+    pub solution: Option<String>,
+    pub use_modern_net: Option<bool>,
+    pub server_args: Vec<String>,
+    pub server_version: Option<String>,
+    pub server_cache_max_age_days: Option<u64>,
+    pub auto_install_dotnet_runtime: Option<bool>,
+    pub analyzer_paths: Vec<String>,
+    pub features: BTreeMap<String, bool>,
+    pub msbuild_evaluation: Option<bool>,
+    pub msbuild_binary_log: bool,
+    pub design_time_builds: bool,
+    pub generate_unity_project_files: bool,
+    pub unity_editor_path: Option<String>,
+    pub large_solution_mode: Option<bool>,
+
+    // Debugging
+    pub debug_adapter: Option<DebugAdapterKind>,
+    pub vsdbg_path: Option<String>,
+    pub debug_target_framework: Option<String>,
+    pub debug_options: Option<serde_json::Map<String, serde_json::Value>>,
+    pub godot_path: Option<String>,
+
+    // Downloads
+    pub local_server_package: Option<String>,
+    pub local_debugger_archive: Option<String>,
+    pub local_unity_debugger_archive: Option<String>,
+    pub server_sha256: Option<String>,
+    pub debugger_sha256: Option<String>,
+    pub unity_debugger_sha256: Option<String>,
+    pub require_verified_downloads: bool,
+
+    // Logging
+    pub enable_debug_logging: bool,
+    pub unsafe_full_logs: bool,
+
+    /// Keys that aren't settings of this struct
+    #[serde(flatten)]
+    unknown: serde_json::Map<String, serde_json::Value>,
+}
+
+impl RoslynSettings {
+    /// Read the settings for a worktree, dropping invalid values. Warnings are discarded; they're
+    /// reported once, when the language server starts.
+    pub fn for_worktree(worktree: &Worktree) -> Self {
+        Self::load(worktree).0
+    }
+
+    /// Read the settings for a worktree, along with a warning for each key that was ignored or migrated
+    pub fn load(worktree: &Worktree) -> (Self, Vec<String>) {
+        let options = |key: &str| {
+            LspSettings::for_worktree(key, worktree)
+                .ok()
+                .and_then(|settings| settings.initialization_options)
+        };
+        Self::from_options(options(SETTINGS_KEY), options(LEGACY_SETTINGS_KEY))
+    }
+
+    /// Build the settings from the raw `initialization_options` of the current and legacy keys.
+    /// Legacy keys are renamed, unknown extension keys and values of the wrong type are dropped,
+    /// and every change is reported as a warning.
+    pub fn from_options(
+        options: Option<serde_json::Value>,
+        legacy: Option<serde_json::Value>,
+    ) -> (Self, Vec<String>) {
+        let mut warnings = Vec::new();
+        let mut merged = serde_json::Map::new();
+
+        let mut sources = Vec::new();
+        if let Some(legacy) = legacy {
+            sources.push((LEGACY_SETTINGS_KEY, legacy));
+        }
+        if let Some(options) = options {
+            sources.push((SETTINGS_KEY, options));
+        }
+        for (source, options) in sources {
+            let serde_json::Value::Object(options) = options else {
+                warnings.push(format!("lsp.{source}.initialization_options must be an object"));
+                continue;
+            };
+            for (key, value) in options {
+                if let Some((_, new_key)) = MIGRATED_KEYS.iter().find(|(old_key, _)| *old_key == key) {
+                    warnings.push(format!("'{key}' is deprecated; use lsp.{SETTINGS_KEY}.initialization_options.{new_key}"));
+                    merged.entry(new_key.to_string()).or_insert(value);
+                } else if source == LEGACY_SETTINGS_KEY {
+                    warnings.push(format!("lsp.{LEGACY_SETTINGS_KEY}.{key} is ignored; move it to lsp.{SETTINGS_KEY}"));
+                } else {
+                    merged.insert(key, value);
+                }
+            }
+        }
+
+        let mut valid = serde_json::Map::new();
+        for (key, value) in merged {
+            // OmniSharp's own option sections start with an uppercase letter
+            if key.starts_with(|c: char| c.is_ascii_uppercase()) {
+                continue;
+            }
+            // Check each value on its own so one bad value doesn't discard the others
+            let single = serde_json::Value::Object(serde_json::Map::from_iter([(key.clone(), value.clone())]));
+            match serde_json::from_value::<Self>(single) {
+                Ok(settings) if !settings.unknown.is_empty() => warnings.push(format!("Unknown setting '{key}'")),
+                Ok(_) => {
+                    valid.insert(key, value);
+                }
+                Err(e) => warnings.push(format!("Ignoring '{key}': {e}")),
+            }
+        }
+
+        let settings = serde_json::from_value(serde_json::Value::Object(valid)).unwrap_or_default();
+        (settings, warnings)
+    }
+
+    /// String-valued download settings (local packages and pinned checksums), by JSON key
+    pub fn download_setting(&self, key: &str) -> Option<&str> {
+        match key {
+            "localServerPackage" => self.local_server_package.as_deref(),
+            "localDebuggerArchive" => self.local_debugger_archive.as_deref(),
+            "localUnityDebuggerArchive" => self.local_unity_debugger_archive.as_deref(),
+            "serverSha256" => self.server_sha256.as_deref(),
+            "debuggerSha256" => self.debugger_sha256.as_deref(),
+            "unityDebuggerSha256" => self.unity_debugger_sha256.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settings_from_options() {
+        let options = serde_json::json!({
+            "solution": "src/App.sln",
+            "serverArgs": ["--loglevel", "debug"],
+            "features": { "analyzers": false },
+            "debugAdapter": "vsdbg",
+            "msbuildBinaryLog": false,
+            "generateUnityProjectFiles": true,
+            "useModernNet": "yes",
+            "serverVersoin": "1.39.11",
+            "RoslynExtensionsOptions": { "enableAnalyzersSupport": false }
+        });
+        let legacy = serde_json::json!({ "enable_debug_logging": true, "solution": "Other.sln" });
+        let (settings, warnings) = RoslynSettings::from_options(Some(options), Some(legacy));
+
+        assert_eq!(settings.solution.as_deref(), Some("src/App.sln"));
+        assert_eq!(settings.server_args, vec!["--loglevel", "debug"]);
+        assert_eq!(settings.features.get("analyzers"), Some(&false));
+        assert_eq!(settings.debug_adapter, Some(DebugAdapterKind::Vsdbg));
+        assert_eq!(settings.use_modern_net, None);
+        assert!(settings.generate_unity_project_files);
+        assert!(settings.enable_debug_logging);
+
+        assert_eq!(warnings.len(), 4, "{warnings:?}");
+        assert!(warnings.iter().any(|w| w.starts_with("'enable_debug_logging' is deprecated")));
+        assert!(warnings.iter().any(|w| w.starts_with("lsp.csharp_roslyn.solution is ignored")));
+        assert!(warnings.iter().any(|w| w.starts_with("Ignoring 'useModernNet'")));
+        assert!(warnings.iter().any(|w| w == "Unknown setting 'serverVersoin'"));
+
+        let (_, warnings) = RoslynSettings::from_options(Some(serde_json::json!({ "debugAdapter": "gdb" })), None);
+        assert!(warnings[0].starts_with("Ignoring 'debugAdapter'"));
+    }
+}
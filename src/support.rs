@@ -0,0 +1,111 @@
+use zed_extension_api::{
+    self as zed, settings::LspSettings, Result, SlashCommandOutput, SlashCommandOutputSection, Worktree,
+};
+
+use crate::debugger::NETCOREDBG_VERSION;
+use crate::logging::LogConfig;
+use crate::omnisharp_download::OMNISHARP_VERSION;
+use crate::project_info::{is_godot_project, is_unity_project};
+use crate::scaffolding::{configured_solution, run_dotnet};
+use crate::settings::RoslynSettings;
+
+/// Append `body` to the report as a section with the given label
+fn push_section(output: &mut SlashCommandOutput, label: &str, body: &str) {
+    if !output.text.is_empty() {
+        output.text.push('\n');
+    }
+    let start = output.text.len();
+    output.text.push_str(&format!("## {label}\n\n{}\n", body.trim_end()));
+    output.sections.push(SlashCommandOutputSection {
+        range: (start..output.text.len()).into(),
+        label: label.to_string(),
+    });
+}
+
+/// Initialization options set for one of the extension's settings keys, pretty-printed
+fn settings_text(key: &str, worktree: &Worktree) -> String {
+    match LspSettings::for_worktree(key, worktree)
+        .ok()
+        .and_then(|settings| settings.initialization_options)
+    {
+        Some(options) => serde_json::to_string_pretty(&options).unwrap_or_else(|_| options.to_string()),
+        None => "(not set)".to_string(),
+    }
+}
+
+/// Run `/csharp-report`: versions, project detection, settings, and `dotnet --info`, ready to
+/// paste into a bug report. Paths under the home directory are shown as `~`.
+pub fn run_csharp_report(worktree: Option<&Worktree>) -> Result<SlashCommandOutput> {
+    let worktree = worktree.ok_or_else(|| "/csharp-report needs an open worktree".to_string())?;
+    let (settings, warnings) = RoslynSettings::load(worktree);
+    let redaction = LogConfig::new(worktree, &settings);
+    let mut output = SlashCommandOutput {
+        text: String::new(),
+        sections: Vec::new(),
+    };
+
+    let (platform, arch) = zed::current_platform();
+    push_section(
+        &mut output,
+        "Extension",
+        &format!(
+            "csharp_roslyn {}\nDefault OmniSharp: {OMNISHARP_VERSION}\nDefault netcoredbg: {NETCOREDBG_VERSION}\nPlatform: {platform:?} {arch:?}",
+            env!("CARGO_PKG_VERSION")
+        ),
+    );
+
+    let kind = if is_unity_project(worktree) {
+        "Unity"
+    } else if is_godot_project(worktree) {
+        "Godot"
+    } else {
+        ".NET"
+    };
+    let solution = configured_solution(worktree, &settings).unwrap_or_else(|| "(auto-detected by OmniSharp)".to_string());
+    push_section(
+        &mut output,
+        "Project",
+        &redaction.redact(&format!("Type: {kind}\nSolution: {solution}")),
+    );
+
+    for key in ["omnisharp-roslyn", "csharp_roslyn"] {
+        push_section(
+            &mut output,
+            &format!("Settings: {key}"),
+            &redaction.redact(&settings_text(key, worktree)),
+        );
+    }
+
+    if !warnings.is_empty() {
+        push_section(&mut output, "Settings warnings", &warnings.join("\n"));
+    }
+
+    let info = match worktree.which("dotnet") {
+        Some(_) => run_dotnet(worktree, &["--info".to_string()]).unwrap_or_else(|e| e),
+        None => "dotnet was not found on PATH".to_string(),
+    };
+    push_section(&mut output, "dotnet --info", &redaction.redact(&info));
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_section() {
+        let mut output = SlashCommandOutput {
+            text: String::new(),
+            sections: Vec::new(),
+        };
+        push_section(&mut output, "Extension", "csharp_roslyn 0.2.0\n");
+        push_section(&mut output, "Project", "Type: Unity");
+
+        assert_eq!(output.text, "## Extension\n\ncsharp_roslyn 0.2.0\n\n## Project\n\nType: Unity\n");
+        assert_eq!(output.sections.len(), 2);
+        let second = &output.sections[1];
+        assert_eq!(second.label, "Project");
+        assert_eq!(&output.text[second.range.start as usize..second.range.end as usize], "## Project\n\nType: Unity\n");
+    }
+}
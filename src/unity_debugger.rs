@@ -0,0 +1,194 @@
+use std::path::{Path, PathBuf};
+use serde::Deserialize;
+use zed_extension_api::{self as zed, Command, Result, Worktree};
+
+use crate::download::{
+    download_verified, expected_checksum, resolve_local_binary, verify_binary, CacheLock, GithubAsset,
+    LocalPackage,
+};
+use crate::logging::debug_log;
+use crate::settings::RoslynSettings;
+
+const UNITY_DEBUG_REPO: &str = "Unity-Technologies/vscode-unity-debug";
+const UNITY_DEBUG_BINARY: &str = "UnityDebug.exe";
+/// Location of the adapter inside the extracted `.vsix`
+const UNITY_DEBUG_BINARY_DIR: &[&str] = &["extension", "bin"];
+/// Written by the Unity Editor while a project is open
+const EDITOR_INSTANCE_FILE: &str = "Library/EditorInstance.json";
+/// Unity's debugger agent listens on this port plus the last three digits of the editor PID
+const DEBUGGER_AGENT_BASE_PORT: u32 = 56000;
+
+/// A running Unity Editor, as described by `Library/EditorInstance.json`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct EditorInstance {
+    pub process_id: u32,
+    #[serde(default)]
+    pub version: String,
+}
+
+pub fn parse_editor_instance(text: &str) -> Option<EditorInstance> {
+    serde_json::from_str(text).ok()
+}
+
+/// Port of the Mono debugger agent of the editor with the given PID
+pub fn debugger_agent_port(process_id: u32) -> u32 {
+    DEBUGGER_AGENT_BASE_PORT + process_id % 1000
+}
+
+/// Configuration attaching to the Unity Editor that has this worktree open. The Unity debug
+/// adapter takes it as a launch request named "Unity Editor" and finds the editor's debugger
+/// agent through `EditorInstance.json`.
+pub fn editor_attach_config(worktree: &Worktree) -> Result<serde_json::Value> {
+    let instance = worktree
+        .read_text_file(EDITOR_INSTANCE_FILE)
+        .ok()
+        .and_then(|text| parse_editor_instance(&text))
+        .ok_or_else(|| {
+            format!("No running Unity Editor found ({EDITOR_INSTANCE_FILE} is missing); open the project in Unity first")
+        })?;
+    debug_log!(
+        worktree,
+        "[csharp_roslyn] Unity Editor {} running as PID {} (debugger agent port {})",
+        instance.version,
+        instance.process_id,
+        debugger_agent_port(instance.process_id)
+    );
+
+    let root_path = worktree.root_path();
+    Ok(serde_json::json!({
+        "request": "launch",
+        "name": "Unity Editor",
+        "type": "unity",
+        "path": format!("{}/{EDITOR_INSTANCE_FILE}", root_path.trim_end_matches(['/', '\\'])),
+    }))
+}
+
+/// Fill the editor attach settings a configuration doesn't set
+pub fn apply_editor_attach_defaults(config: &mut serde_json::Value, worktree: &Worktree) -> Result<()> {
+    let Some(object) = config.as_object_mut() else {
+        return Err("Debug configuration must be an object".to_string());
+    };
+    if object.contains_key("path") {
+        return Ok(());
+    }
+    if let serde_json::Value::Object(defaults) = editor_attach_config(worktree)? {
+        for (key, value) in defaults {
+            object.entry(key).or_insert(value);
+        }
+    }
+    Ok(())
+}
+
+/// Locate or download the Unity debug adapter. It's a .NET Framework program, so it runs
+/// through Mono outside Windows.
+pub fn ensure_unity_debug_adapter(
+    worktree: &Worktree,
+    settings: &RoslynSettings,
+    user_provided_path: Option<String>,
+) -> Result<Command> {
+    let adapter = match user_provided_path {
+        Some(path) => resolve_local_binary(&path, UNITY_DEBUG_BINARY),
+        None => match LocalPackage::from_settings(settings, "localUnityDebuggerArchive") {
+            Some(LocalPackage::Path(path)) => resolve_local_binary(&path, UNITY_DEBUG_BINARY),
+            Some(LocalPackage::Url(url)) => download_adapter(worktree, settings, &url, "local", None)?,
+            None => {
+                let release = zed::latest_github_release(
+                    UNITY_DEBUG_REPO,
+                    zed::GithubReleaseOptions {
+                        require_assets: true,
+                        pre_release: false,
+                    },
+                )?;
+                let asset = release
+                    .assets
+                    .iter()
+                    .find(|asset| asset.name.ends_with(".vsix"))
+                    .ok_or_else(|| format!("No .vsix asset in {UNITY_DEBUG_REPO} release {}", release.version))?;
+                let (owner, repo) = UNITY_DEBUG_REPO.split_once('/').unwrap_or_default();
+                let github_asset = GithubAsset {
+                    owner,
+                    repo,
+                    tag: &release.version,
+                    name: &asset.name,
+                };
+                download_adapter(worktree, settings, &asset.download_url, &release.version, Some(github_asset))?
+            }
+        },
+    };
+    debug_log!(worktree, "[csharp_roslyn] Using Unity debug adapter: {adapter}");
+
+    let (platform, _) = zed::current_platform();
+    if platform == zed::Os::Windows {
+        return Ok(Command {
+            command: adapter,
+            args: Vec::new(),
+            env: Default::default(),
+        });
+    }
+    let mono = worktree
+        .which("mono")
+        .ok_or_else(|| "The Unity debug adapter requires Mono, but `mono` was not found on PATH".to_string())?;
+    Ok(Command {
+        command: mono,
+        args: vec![adapter],
+        env: Default::default(),
+    })
+}
+
+fn download_adapter(
+    worktree: &Worktree,
+    settings: &RoslynSettings,
+    download_url: &str,
+    version: &str,
+    github_asset: Option<GithubAsset>,
+) -> Result<String> {
+    let cache_dir = Path::new("cache").join("unity-debug").join(version);
+    let binary = UNITY_DEBUG_BINARY_DIR
+        .iter()
+        .fold(cache_dir.clone(), |path, part| path.join(part))
+        .join(UNITY_DEBUG_BINARY);
+
+    // Another Zed window may be installing the same version; wait for it before checking again
+    let _lock = if binary.exists() { None } else { Some(CacheLock::acquire(&cache_dir)?) };
+    if !binary.exists() {
+        std::fs::create_dir_all(&cache_dir)
+            .map_err(|e| format!("Failed to create Unity debug adapter cache directory: {e}"))?;
+        let checksum = expected_checksum(worktree, settings, "unityDebuggerSha256", github_asset)?;
+        debug_log!(worktree, "[csharp_roslyn] Downloading Unity debug adapter from: {download_url}");
+        let result = download_verified(download_url, &cache_dir, checksum.as_deref())
+            .and_then(|_| verify_binary(&binary));
+        if let Err(e) = result {
+            let _ = std::fs::remove_dir_all(&cache_dir);
+            return Err(format!("Failed to install the Unity debug adapter: {e}"));
+        }
+    }
+
+    let absolute_path: PathBuf = if binary.is_absolute() {
+        binary
+    } else {
+        std::env::current_dir()
+            .map_err(|e| format!("Failed to get current directory: {e}"))?
+            .join(&binary)
+    };
+    Ok(absolute_path.to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_editor_instance() {
+        let text = r#"{
+  "process_id": 48213,
+  "version": "2022.3.10f1",
+  "app_path": "/Applications/Unity/Hub/Editor/2022.3.10f1/Unity.app",
+  "app_contents_path": "/Applications/Unity/Hub/Editor/2022.3.10f1/Unity.app/Contents"
+}"#;
+        let instance = parse_editor_instance(text).unwrap();
+        assert_eq!(instance.process_id, 48213);
+        assert_eq!(instance.version, "2022.3.10f1");
+        assert_eq!(debugger_agent_port(instance.process_id), 56213);
+        assert!(parse_editor_instance("{}").is_none());
+    }
+}